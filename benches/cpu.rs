@@ -0,0 +1,94 @@
+//! Benchmark that measure how many instructions the cpu execute per second
+//!
+//! Run with `cargo bench --bench cpu`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rust_nes_emulator::core::cpu::asm;
+use rust_nes_emulator::core::cpu::memory::Memory;
+use rust_nes_emulator::core::cpu::{BoxedCpu, Cpu};
+use rust_nes_emulator::core::types::*;
+
+const INSTRUCTIONS: u64 = 10_000;
+
+// A tight loop that touches zero page, stack and absolute memory.
+const PROGRAM: &str = "
+            .org $8000
+    loop:   INX
+            DEY
+            LDA $10,X
+            ADC #$01
+            STA $0200,Y
+            PHA
+            PLA
+            BNE loop
+            JMP loop
+            .org $FFFC
+            .word loop
+";
+
+// Smallest loop, so decoding dominates the time.
+const TIGHT_LOOP: &str = "
+            .org $8000
+    loop:   DEX
+            BNE loop
+            JMP loop
+            .org $FFFC
+            .word loop
+";
+
+struct Ram {
+    bytes: Vec<Byte>,
+}
+
+impl Ram {
+    fn new(source: &str) -> Self {
+        let mut ram = Self { bytes: vec![0; 0x10000] };
+        asm::assemble(source).unwrap().write_to(&mut ram);
+        ram
+    }
+}
+
+impl Memory for Ram {
+    fn read_byte(&self, addr: Address) -> Byte {
+        self.bytes[addr as usize]
+    }
+
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        self.bytes[addr as usize] = value;
+    }
+}
+
+fn run<M: Memory>(cpu: &mut Cpu<M>) {
+    for _ in 0..INSTRUCTIONS {
+        cpu.step().unwrap();
+    }
+}
+
+fn bench_instructions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("instructions");
+    group.throughput(Throughput::Elements(INSTRUCTIONS));
+
+    let mut boxed: BoxedCpu = Cpu::new(Box::new(Ram::new(PROGRAM)));
+    boxed.power_on();
+    group.bench_function("boxed", |b| b.iter(|| run(&mut boxed)));
+
+    let mut generic = Cpu::new(Ram::new(PROGRAM));
+    generic.power_on();
+    group.bench_function("generic", |b| b.iter(|| run(&mut generic)));
+
+    group.finish();
+}
+
+fn bench_tight_loop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tight_loop");
+    group.throughput(Throughput::Elements(INSTRUCTIONS));
+
+    let mut cpu = Cpu::new(Ram::new(TIGHT_LOOP));
+    cpu.power_on();
+    group.bench_function("generic", |b| b.iter(|| run(&mut cpu)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_instructions, bench_tight_loop);
+criterion_main!(benches);
@@ -2,3 +2,4 @@ pub mod cpu;
 pub mod bus;
 pub mod types;
 pub mod cartridge;
+pub mod mapper;
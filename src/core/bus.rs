@@ -2,29 +2,73 @@
 
 #![allow(dead_code)]
 
+use std::cell::Cell;
 use super::cpu::memory::Memory;
 use super::cartridge::Cartoridge;
+use super::mapper::{self, Mapper, MapperError};
 use super::types::{Byte, Address};
 
 pub struct Bus {
     ram: [Byte; 0x0800],
-    cartridge: Cartoridge,
+    mapper: Box<dyn Mapper>,
+    // Last value on the cpu data bus. Nothing drives the bus on reads of
+    // unmapped addresses, so they return this value.
+    open_bus: Cell<Byte>,
+    // Last value on the bus between cpu and ppu registers. Reads of write only
+    // registers and unused bits of ppu status return this value.
+    ppu_open_bus: Cell<Byte>,
 }
 
 impl Bus {
-    pub fn new(cartridge: Cartoridge) -> Self {
+    /// Build the mapper `cartridge` requires, and connect it to the bus.
+    pub fn new(cartridge: Cartoridge) -> Result<Self, MapperError> {
+        Ok(Self::with_mapper(mapper::new_mapper(cartridge)?))
+    }
+
+    pub fn with_mapper(mapper: Box<dyn Mapper>) -> Self {
         Self {
             ram: [0; 0x0800],
-            cartridge,
+            mapper,
+            open_bus: Cell::new(0),
+            ppu_open_bus: Cell::new(0),
         }
     }
 
-    fn read_prg_rom(&self, addr: Address) -> u8 {
-        let mut addr = addr - 0x8000;
-        if self.cartridge.prg_rom.len() == 0x4000 && addr >= 0x4000 {
-            addr = addr & 0x4000;
+    pub fn mapper(&self) -> &dyn Mapper {
+        self.mapper.as_ref()
+    }
+
+    pub fn mapper_mut(&mut self) -> &mut dyn Mapper {
+        self.mapper.as_mut()
+    }
+
+    /// Return true if the cartridge asserts irq line.
+    pub fn irq(&self) -> bool {
+        self.mapper.irq()
+    }
+
+    // Return the value on the data bus without updating latches.
+    fn read(&self, addr: Address) -> Byte {
+        match addr {
+            RAM_BEGIN..=RAM_END => {
+                let mirror_down_addr = addr & 0b00000111_11111111;
+                self.ram[mirror_down_addr as usize]
+            }
+            PPU_BEGIN..=PPU_END => {
+                let mirror_down_addr = addr & 0b00100000_00000111;
+                match mirror_down_addr {
+                    // Vblank, sprite 0 hit and sprite overflow flags are not implemented yet
+                    PPU_STATUS => self.ppu_open_bus.get() & !PPU_STATUS_MASK,
+                    OAM_DATA | PPU_DATA => todo!("PPU is not implemented yet"),
+                    // Write only registers
+                    _ => self.ppu_open_bus.get(),
+                }
+            }
+            // Controllers are not connected yet, so they drive 0.
+            CONTROLLER_1 | CONTROLLER_2 => self.open_bus.get() & !CONTROLLER_MASK,
+            CARTRIDGE_BEGIN..=CARTRIDGE_END => self.mapper.cpu_read(addr).unwrap_or(self.open_bus.get()),
+            _ => self.open_bus.get(),
         }
-        self.cartridge.prg_rom[addr as usize]
     }
 }
 
@@ -32,42 +76,133 @@ const RAM_BEGIN: Address = 0x0000;
 const RAM_END:   Address = 0x1FFF;
 const PPU_BEGIN: Address = 0x2000;
 const PPU_END:   Address = 0x3FFF;
+const CARTRIDGE_BEGIN: Address = 0x4020;
+const CARTRIDGE_END:   Address = 0xFFFF;
+
+const PPU_STATUS: Address = 0x2002;
+const OAM_DATA:   Address = 0x2004;
+const PPU_DATA:   Address = 0x2007;
+
+const CONTROLLER_1: Address = 0x4016;
+const CONTROLLER_2: Address = 0x4017;
+
+// Bits driven by ppu status. The others are ppu open bus.
+const PPU_STATUS_MASK: Byte = 0b1110_0000;
+// Bits driven by controller ports. The others are open bus.
+const CONTROLLER_MASK: Byte = 0b0001_1111;
 
 impl Memory for Bus {
     fn read_byte(&self, addr: Address) -> Byte {
-        match addr {
-            RAM_BEGIN..=RAM_END => {
-                let mirror_down_addr = addr & 0b00000111_11111111;
-                self.ram[mirror_down_addr as usize]
-            }
-            PPU_BEGIN..=PPU_END => {
-                let _mirror_down_addr = addr & 0b00100000_00000111;
-                todo!("PPU is not implemented yet")
-            }
-            0x8000..=0xFFFF => self.read_prg_rom(addr),
-            _ => {
-                println!("This access is ignored: 0x{:x}", addr);
-                0
-            }
+        let value = self.read(addr);
+        if let PPU_BEGIN..=PPU_END = addr {
+            self.ppu_open_bus.set(value);
         }
+        self.open_bus.set(value);
+        value
     }
 
     fn write_byte(&mut self, addr: Address, value: Byte) {
+        self.open_bus.set(value);
         match addr {
             RAM_BEGIN..=RAM_END => {
                 let mirror_down_addr = addr & 0b00000111_11111111;
                 self.ram[mirror_down_addr as usize] = value;
             }
-            PPU_BEGIN..=PPU_END => {
-                let _mirror_down_addr = addr & 0b00100000_00000111;
-                todo!("PPU is not implemented yet")
-            }
-            0x8000..=0xFFFF => {
-                panic!("Attempt to write to cartridge rom space");
-            }
-            _ => {
-                println!("This access is ignored: 0x{:x}", addr);
-            }
+            // Registers are not implemented yet, but the value remains on ppu bus.
+            PPU_BEGIN..=PPU_END => self.ppu_open_bus.set(value),
+            CARTRIDGE_BEGIN..=CARTRIDGE_END => self.mapper.cpu_write(addr, value),
+            // Writing to unmapped address has no effect
+            _ => {}
         }
     }
+
+    fn peek_byte(&self, addr: Address) -> Byte {
+        self.read(addr)
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.mapper.tick(cycles);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::cartridge::Mirroring;
+    use crate::core::cpu::Cpu;
+
+    // 32KB rom filled with `fill`, whose reset vector is 0x8000
+    fn new_bus(program: &[Byte], fill: Byte) -> Bus {
+        let mut prg_rom = vec![fill; 0x8000];
+        prg_rom[..program.len()].copy_from_slice(program);
+        prg_rom[0x7FFC] = 0x00;
+        prg_rom[0x7FFD] = 0x80;
+        Bus::new(Cartoridge { prg_rom, chr_rom: Vec::new(), mapper: 0, mirroring: Mirroring::Horizontal, ..Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn test_unmapped_read_returns_last_value() {
+        let mut bus = new_bus(&[], 0x5A);
+
+        assert_eq!(bus.read_byte(0x8000), 0x5A);
+        assert_eq!(bus.read_byte(0x4018), 0x5A);
+        assert_eq!(bus.read_byte(0x5FFF), 0x5A);
+
+        bus.write_byte(0x0000, 0x12);
+        assert_eq!(bus.read_byte(0x6000), 0x12);
+    }
+
+    #[test]
+    fn test_peek_does_not_update_open_bus() {
+        let bus = new_bus(&[], 0x5A);
+        bus.read_byte(0x0000);
+
+        assert_eq!(bus.peek_byte(0x8000), 0x5A);
+        assert_eq!(bus.peek_byte(0x4018), 0x00);
+    }
+
+    #[test]
+    fn test_controller_partial_open_bus() {
+        let bus = new_bus(&[], 0x41);
+        bus.read_byte(0x8000);
+
+        assert_eq!(bus.read_byte(0x4016), 0x40);
+        assert_eq!(bus.read_byte(0x4017), 0x40);
+    }
+
+    #[test]
+    fn test_ppu_partial_open_bus() {
+        let mut bus = new_bus(&[], 0x00);
+        bus.write_byte(0x2000, 0xFF);
+
+        assert_eq!(bus.read_byte(0x2005), 0xFF);
+        assert_eq!(bus.read_byte(0x3FFA), 0x1F);
+        // Ppu bus is not affected by other reads
+        assert_eq!(bus.read_byte(0x8000), 0x00);
+        assert_eq!(bus.read_byte(0x2001), 0x1F);
+    }
+
+    #[test]
+    fn test_unsupported_mapper() {
+        let cartridge = Cartoridge { prg_rom: vec![0; 0x4000], chr_rom: Vec::new(), mapper: 5, mirroring: Mirroring::Horizontal, ..Default::default() };
+
+        assert_eq!(Bus::new(cartridge).err(), Some(MapperError::Unsupported { mapper: 5 }));
+    }
+
+    #[test]
+    fn test_empty_prg_rom() {
+        let cartridge = Cartoridge { prg_rom: Vec::new(), chr_rom: Vec::new(), mapper: 0, mirroring: Mirroring::Horizontal, ..Default::default() };
+
+        assert_eq!(Bus::new(cartridge).err(), Some(MapperError::EmptyPrgRom));
+    }
+
+    // The last value on the bus is the high byte of the operand.
+    #[test]
+    fn test_cpu_reads_open_bus() {
+        let mut cpu = Cpu::new(new_bus(&[0xAD, 0x00, 0x50], 0x00));
+        cpu.power_on();
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.a(), 0x50);
+    }
 }
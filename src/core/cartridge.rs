@@ -3,57 +3,256 @@
 #![allow(dead_code)]
 
 const NES_IDENTIFIER: [u8; 4]  = [0x4E, 0x45, 0x53, 0x1A];
+const HEADER_SIZE: usize       = 16;
+const TRAINER_SIZE: usize      = 0x0200;
 const PRG_ROM_PAGE_SIZE: usize = 0x4000;
 const CHR_ROM_PAGE_SIZE: usize = 0x2000;
+const PRG_RAM_PAGE_SIZE: usize = 0x2000;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Mirroring {
     Vertical,
+    #[default]
     Horizontal,
     FourScreen,
+    /// All nametables refer the first nametable
+    SingleScreenLower,
+    /// All nametables refer the second nametable
+    SingleScreenUpper,
 }
 
+/// Timing of cpu and ppu the cartridge is made for
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Timing {
+    #[default]
+    Ntsc,
+    Pal,
+    /// Works on both NTSC and PAL
+    MultipleRegion,
+    Dendy,
+}
+
+/// Console the cartridge is made for
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConsoleType {
+    /// NES or Famicom
+    #[default]
+    Nes,
+    /// Nintendo Vs. System with PPU type and hardware type in NES 2.0 header
+    VsSystem { ppu_type: u8, hardware_type: u8 },
+    Playchoice10,
+    /// Console type in NES 2.0 header such as Famiclone with decimal mode
+    Extended(u8),
+}
+
+#[derive(Default)]
 pub struct Cartoridge {
     pub prg_rom: Vec<u8>,
     pub chr_rom: Vec<u8>,
-    pub mapper: u8,
+    /// Mapper number, which is 12-bit in NES 2.0 and 8-bit in iNES
+    pub mapper: u16,
+    /// Variant of the mapper. This is always 0 in iNES.
+    pub submapper: u8,
     pub mirroring: Mirroring,
+    /// Whether the cartridge has battery to keep PRG RAM or other memory
+    pub has_battery: bool,
+    /// Sizes in bytes of volatile and non-volatile PRG RAM
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    /// Sizes in bytes of volatile and non-volatile CHR RAM
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub timing: Timing,
+    pub console_type: ConsoleType,
+    /// Default expansion device numbered as NES 2.0. 0 means unspecified,
+    /// and 1 means standard controllers.
+    pub expansion_device: u8,
+    /// Whether the header is NES 2.0
+    pub is_nes2: bool,
 }
 
 impl Cartoridge {
     pub fn new(bytes: Vec<u8>) -> Result<Cartoridge, String> {
-        if bytes[0..4] != NES_IDENTIFIER {
+        if bytes.len() < HEADER_SIZE || bytes[0..4] != NES_IDENTIFIER {
             return Err("The bytes is not iNES format".to_string());
-        } else {
-            let mapper = (bytes[7] & 0b1111_0000) | (bytes[6] >> 4);
-
-            let ines_version = bytes[7] >> 2;
-            if ines_version != 0 {
-                return Err("iNES 2.0 is not supported".to_string());
-            }
-
-            let is_four_screen = bytes[6] & 0b1000 != 0;
-            let is_vertical    = bytes[6] & 0b0001 != 0;
-            let mirroring      = match (is_four_screen, is_vertical) {
-                (true, _)      => Mirroring::FourScreen,
-                (false, true)  => Mirroring::Vertical,
-                (false, false) => Mirroring::Horizontal,
+        }
+
+        let is_nes2 = bytes[7] & 0b1100 == 0b1000;
+
+        let is_four_screen = bytes[6] & 0b1000 != 0;
+        let is_vertical    = bytes[6] & 0b0001 != 0;
+        let mirroring      = match (is_four_screen, is_vertical) {
+            (true, _)      => Mirroring::FourScreen,
+            (false, true)  => Mirroring::Vertical,
+            (false, false) => Mirroring::Horizontal,
+        };
+
+        let console_type = match bytes[7] & 0b11 {
+            0 => ConsoleType::Nes,
+            1 if is_nes2 => ConsoleType::VsSystem { ppu_type: bytes[13] & 0x0F, hardware_type: bytes[13] >> 4 },
+            1 => ConsoleType::VsSystem { ppu_type: 0, hardware_type: 0 },
+            2 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Extended(if is_nes2 { bytes[13] & 0x0F } else { 0 }),
+        };
+
+        let mut cartridge = Cartoridge {
+            mapper: ((bytes[7] & 0b1111_0000) | (bytes[6] >> 4)) as u16,
+            mirroring,
+            has_battery: bytes[6] & 0b0010 != 0,
+            console_type,
+            is_nes2,
+            ..Default::default()
+        };
+
+        let (prg_rom_size, chr_rom_size) = if is_nes2 {
+            cartridge.mapper          |= ((bytes[8] & 0x0F) as u16) << 8;
+            cartridge.submapper        = bytes[8] >> 4;
+            cartridge.prg_ram_size     = shift_size(bytes[10] & 0x0F);
+            cartridge.prg_nvram_size   = shift_size(bytes[10] >> 4);
+            cartridge.chr_ram_size     = shift_size(bytes[11] & 0x0F);
+            cartridge.chr_nvram_size   = shift_size(bytes[11] >> 4);
+            cartridge.timing           = match bytes[12] & 0b11 {
+                0 => Timing::Ntsc,
+                1 => Timing::Pal,
+                2 => Timing::MultipleRegion,
+                _ => Timing::Dendy,
             };
+            cartridge.expansion_device = bytes[15] & 0b0011_1111;
 
-            let prg_rom_size  = bytes[4] as usize * PRG_ROM_PAGE_SIZE;
-            let chr_rom_size  = bytes[5] as usize * CHR_ROM_PAGE_SIZE;
+            (rom_size(bytes[4], bytes[9] & 0x0F, PRG_ROM_PAGE_SIZE)?, rom_size(bytes[5], bytes[9] >> 4, CHR_ROM_PAGE_SIZE)?)
+        } else {
+            // iNES has only PRG RAM size, where 0 means 8KB for compatibility.
+            cartridge.prg_ram_size = bytes[8].max(1) as usize * PRG_RAM_PAGE_SIZE;
+            cartridge.chr_ram_size = if bytes[5] == 0 { CHR_ROM_PAGE_SIZE } else { 0 };
+            cartridge.timing       = if bytes[9] & 1 == 0 { Timing::Ntsc } else { Timing::Pal };
+
+            (bytes[4] as usize * PRG_ROM_PAGE_SIZE, bytes[5] as usize * CHR_ROM_PAGE_SIZE)
+        };
 
-            let skip_trainer  = bytes[6] & 0b0100 != 0;
+        if prg_rom_size == 0 {
+            return Err("PRG ROM size in the header is 0".to_string());
+        }
 
-            let prg_rom_start = 16 + if skip_trainer { 0x0200 } else { 0 };
-            let chr_rom_start = prg_rom_start + prg_rom_size;
+        let skip_trainer  = bytes[6] & 0b0100 != 0;
 
-            Ok(Cartoridge {
-                prg_rom: bytes[prg_rom_start..(prg_rom_start + prg_rom_size)].to_vec(),
-                chr_rom: bytes[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec(),
-                mapper,
-                mirroring,
-            })
+        let prg_rom_start = HEADER_SIZE + if skip_trainer { TRAINER_SIZE } else { 0 };
+        let too_large     = || "ROM size in the header is too large".to_string();
+        let chr_rom_start = prg_rom_start.checked_add(prg_rom_size).ok_or_else(too_large)?;
+        let chr_rom_end   = chr_rom_start.checked_add(chr_rom_size).ok_or_else(too_large)?;
+        if bytes.len() < chr_rom_end {
+            return Err("The bytes is shorter than ROM sizes in the header".to_string());
         }
+
+        cartridge.prg_rom = bytes[prg_rom_start..chr_rom_start].to_vec();
+        cartridge.chr_rom = bytes[chr_rom_start..chr_rom_end].to_vec();
+        Ok(cartridge)
+    }
+}
+
+// NES 2.0 ROM size is number of pages, or exponent-multiplier notation if msb nibble is 0xF.
+fn rom_size(lsb: u8, msb: u8, page_size: usize) -> Result<usize, String> {
+    if msb != 0x0F {
+        return Ok((((msb as usize) << 8) | lsb as usize) * page_size);
+    }
+
+    // 2^exponent * (multiplier * 2 + 1) bytes
+    let exponent   = (lsb >> 2) as u32;
+    let multiplier = (lsb & 0b11) as usize * 2 + 1;
+    1usize.checked_shl(exponent)
+        .and_then(|size| size.checked_mul(multiplier))
+        .ok_or_else(|| "ROM size in the header is too large".to_string())
+}
+
+// NES 2.0 RAM size is 64 << shift bytes, where 0 means no RAM.
+fn shift_size(shift: u8) -> usize {
+    if shift == 0 { 0 } else { 64 << shift }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Header followed by ROMs filled with 0x01 for PRG and 0x02 for CHR
+    fn new_rom(header: [u8; 16], prg_rom_size: usize, chr_rom_size: usize) -> Vec<u8> {
+        let mut bytes = header.to_vec();
+        bytes.extend(vec![0x01; prg_rom_size]);
+        bytes.extend(vec![0x02; chr_rom_size]);
+        bytes
+    }
+
+    #[test]
+    fn test_ines() {
+        let header    = [0x4E, 0x45, 0x53, 0x1A, 2, 1, 0x13, 0x40, 0, 0, 0, 0, 0, 0, 0, 0];
+        let cartridge = Cartoridge::new(new_rom(header, 0x8000, 0x2000)).unwrap();
+
+        assert!(!cartridge.is_nes2);
+        assert_eq!(cartridge.mapper, 0x41);
+        assert_eq!(cartridge.mirroring, Mirroring::Vertical);
+        assert!(cartridge.has_battery);
+        assert_eq!(cartridge.prg_rom, vec![0x01; 0x8000]);
+        assert_eq!(cartridge.chr_rom, vec![0x02; 0x2000]);
+        assert_eq!(cartridge.prg_ram_size, 0x2000);
+        assert_eq!(cartridge.chr_ram_size, 0);
+        assert_eq!(cartridge.timing, Timing::Ntsc);
+    }
+
+    #[test]
+    fn test_nes2() {
+        let header    = [0x4E, 0x45, 0x53, 0x1A, 0x02, 0x00, 0x48, 0x19, 0x52, 0x00, 0x70, 0x07, 0x03, 0x00, 0x00, 0x02];
+        let cartridge = Cartoridge::new(new_rom(header, 0x8000, 0)).unwrap();
+
+        assert!(cartridge.is_nes2);
+        assert_eq!(cartridge.mapper, 0x214);
+        assert_eq!(cartridge.submapper, 5);
+        assert_eq!(cartridge.mirroring, Mirroring::FourScreen);
+        assert_eq!(cartridge.console_type, ConsoleType::VsSystem { ppu_type: 0, hardware_type: 0 });
+        assert_eq!(cartridge.prg_rom.len(), 0x8000);
+        assert!(cartridge.chr_rom.is_empty());
+        assert_eq!(cartridge.prg_ram_size, 0);
+        assert_eq!(cartridge.prg_nvram_size, 0x2000);
+        assert_eq!(cartridge.chr_ram_size, 0x2000);
+        assert_eq!(cartridge.chr_nvram_size, 0);
+        assert_eq!(cartridge.timing, Timing::Dendy);
+        assert_eq!(cartridge.expansion_device, 2);
+    }
+
+    #[test]
+    fn test_nes2_rom_size() {
+        // 0x100 + 0x01 pages of PRG ROM, and 2^4 * 3 bytes of CHR ROM
+        let header    = [0x4E, 0x45, 0x53, 0x1A, 0x01, 0x11, 0x00, 0x08, 0x00, 0xF1, 0, 0, 0, 0, 0, 0];
+        let cartridge = Cartoridge::new(new_rom(header, 0x101 * PRG_ROM_PAGE_SIZE, 48)).unwrap();
+
+        assert_eq!(cartridge.prg_rom.len(), 0x101 * PRG_ROM_PAGE_SIZE);
+        assert_eq!(cartridge.chr_rom, vec![0x02; 48]);
+    }
+
+    #[test]
+    fn test_nes2_console_type_and_timing() {
+        let header    = [0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0x0B, 0, 0, 0, 0, 0x02, 0x03, 0, 0];
+        let cartridge = Cartoridge::new(new_rom(header, 0x4000, 0)).unwrap();
+
+        assert_eq!(cartridge.console_type, ConsoleType::Extended(3));
+        assert_eq!(cartridge.timing, Timing::MultipleRegion);
+    }
+
+    #[test]
+    fn test_invalid_rom() {
+        let header = [0x4E, 0x45, 0x53, 0x1A, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        assert!(Cartoridge::new(vec![0x4E, 0x45, 0x53]).is_err());
+        assert!(Cartoridge::new(new_rom(header, 0x8000, 0)).is_err());
+
+        let header = [0x4E, 0x45, 0x53, 0x1A, 0xFF, 0, 0, 0x08, 0, 0x0F, 0, 0, 0, 0, 0, 0];
+        assert!(Cartoridge::new(new_rom(header, 0, 0)).is_err());
+
+        // No PRG ROM, in both iNES and NES 2.0
+        let header = [0x4E, 0x45, 0x53, 0x1A, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(Cartoridge::new(new_rom(header, 0, 0x2000)).is_err());
+        let header = [0x4E, 0x45, 0x53, 0x1A, 0, 1, 0, 0x08, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(Cartoridge::new(new_rom(header, 0, 0x2000)).is_err());
+
+        // Sum of PRG and CHR ROM sizes overflows
+        let header = [0x4E, 0x45, 0x53, 0x1A, 0xFC, 0xFC, 0, 0x08, 0, 0xFF, 0, 0, 0, 0, 0, 0];
+        assert!(Cartoridge::new(new_rom(header, 0, 0)).is_err());
     }
 }
@@ -4,26 +4,71 @@
 // - [x] We need to do refactoring.
 // - [x] Overflow in adc and sbc may incorrect.
 // - [x] Implement jmp indirect addressing mode error.
-// - [ ] Implement zeropage addressing mode error ($xx, x, $xx, y, $(xx, x), $(xx), y).
-// - [ ] Implement brk, jsr, rti, rts. -- Need more infomatin about their behavior
-// - [ ] Consider that BREAK2 in p register is always on.
+// - [x] Implement zeropage addressing mode error ($xx, x, $xx, y, $(xx, x), $(xx), y).
+// - [x] Implement brk, jsr, rti, rts.
+// - [x] Consider that BREAK2 in p register is always on.
 
+pub mod asm;
+pub mod cycle;
+pub mod disasm;
 pub mod memory;
+pub mod trace;
 
+mod error;
+mod interrupt;
 mod opcode;
 mod register;
+mod state;
+mod variant;
+
+#[cfg(test)]
+mod processor_tests;
+
+pub use self::cycle::CycleCpu;
+pub use self::error::CpuError;
+pub use self::interrupt::Interrupt;
+pub use self::opcode::{AddressingMode, Mnemonic, Opcode};
+pub use self::state::CpuState;
+pub use self::variant::CpuVariant;
 
 use self::memory::Memory;
+use self::trace::Tracer;
 use self::opcode::*;
 use self::register::*;
 use crate::core::types::*;
 
-pub struct Cpu {
+/// The cpu that access memory through trait object
+pub type BoxedCpu = Cpu<Box<dyn Memory>>;
+
+/// The summary of an instruction executed by [`Cpu::step`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepInfo {
+    pub opcode: Opcode,
+    pub name: Mnemonic,
+    pub mode: AddressingMode,
+    /// Cycles consumed by the instruction, including serviced interrupt
+    pub cycles: u64,
+    /// Interrupt serviced before the instruction was executed
+    pub interrupt: Option<Interrupt>,
+}
+
+/// The cpu that access memory `M`.
+///
+/// Memory access is statically dispatched, so use concrete memory type for speed.
+/// `BoxedCpu` can be used when the memory is decided at runtime.
+pub struct Cpu<M: Memory> {
     regs: Register,
-    mem:  Box<dyn Memory>,
+    mem:  M,
+    variant: CpuVariant,
+    cycles: u64,
+    nmi_line:    bool,
+    nmi_pending: bool,
+    irq_line:    bool,
+    waiting:     bool,
+    tracer: Option<Box<dyn Tracer>>,
 }
 
-impl Cpu {
+impl<M: Memory> Cpu<M> {
     // Get opcode and increment its program counter
     fn fetch_opcode(&mut self) -> Opcode {
         let opcode = self.mem.read_byte(self.regs.pc);
@@ -34,69 +79,197 @@ impl Cpu {
     // Return address where contain data, or return 0
     // After get address, increments its program counter
     fn fetch_address<'a>(&mut self, info: &'a OpcodeInfo) -> (Address, &'a AddressingMode) {
-        // Fetch address
-        let address = match info.mode {
-            AddressingMode::Accumulator | AddressingMode::Implied => 0,
-            AddressingMode::Absolute  => self.fetch_absolute_with_index(0),
-            AddressingMode::AbsoluteX => self.fetch_absolute_with_index(self.regs.x),
-            AddressingMode::AbsoluteY => self.fetch_absolute_with_index(self.regs.y),
-            AddressingMode::Immediate => self.fetch_immediate(),
-            AddressingMode::Indirect  => self.fetch_indirect(),
-            AddressingMode::IndirectX => self.fetch_indirect_with_index(self.regs.x, 0),
-            AddressingMode::IndirectY => self.fetch_indirect_with_index(0, self.regs.y),
-            AddressingMode::Relative  => self.fetch_relative(),
-            AddressingMode::ZeroPage  => self.fetch_zero_page_with_index(0),
-            AddressingMode::ZeroPageX => self.fetch_zero_page_with_index(self.regs.x),
-            AddressingMode::ZeroPageY => self.fetch_zero_page_with_index(self.regs.y),
-        };
+        let address = self.effective_address(&self.mem, &info.mode, self.regs.pc);
 
         // Increase program counter and return reslut
         self.regs.pc = self.regs.pc.wrapping_add(info.byte as Address - 1);
         (address, &info.mode)
     }
 
-    fn fetch_absolute_with_index(&self, index: Byte) -> Address {
-        self.mem.read_word(self.regs.pc.wrapping_add(index as Word)) as Address
+    // Return address where contain data with operand at `operand` of `mem`, or return 0.
+    // This doesn't change any state of the cpu, so it can be used to peek the address.
+    fn effective_address(&self, mem: &impl Memory, mode: &AddressingMode, operand: Address) -> Address {
+        match mode {
+            AddressingMode::Accumulator | AddressingMode::Implied => 0,
+            AddressingMode::Absolute  => self.fetch_absolute_with_index(mem, operand, 0),
+            AddressingMode::AbsoluteX => self.fetch_absolute_with_index(mem, operand, self.regs.x),
+            AddressingMode::AbsoluteY => self.fetch_absolute_with_index(mem, operand, self.regs.y),
+            AddressingMode::Immediate => self.fetch_immediate(operand),
+            AddressingMode::Indirect if self.variant.is_cmos() => mem.read_word(mem.read_word(operand)),
+            AddressingMode::Indirect  => self.fetch_indirect(mem, operand),
+            AddressingMode::IndirectX => self.fetch_indirect_with_index(mem, operand, self.regs.x, 0),
+            AddressingMode::IndirectY => self.fetch_indirect_with_index(mem, operand, 0, self.regs.y),
+            AddressingMode::Relative  => self.fetch_relative(mem, operand),
+            AddressingMode::ZeroPage  => self.fetch_zero_page_with_index(mem, operand, 0),
+            AddressingMode::ZeroPageX => self.fetch_zero_page_with_index(mem, operand, self.regs.x),
+            AddressingMode::ZeroPageY => self.fetch_zero_page_with_index(mem, operand, self.regs.y),
+            AddressingMode::ZeroPageIndirect  => self.fetch_indirect_with_index(mem, operand, 0, 0),
+            AddressingMode::AbsoluteIndirectX => {
+                mem.read_word(self.fetch_absolute_with_index(mem, operand, self.regs.x))
+            }
+            // Branch target is fetched by the instruction
+            AddressingMode::ZeroPageRelative  => self.fetch_zero_page_with_index(mem, operand, 0),
+        }
+    }
+
+    fn fetch_absolute_with_index(&self, mem: &impl Memory, operand: Address, index: Byte) -> Address {
+        mem.read_word(operand).wrapping_add(index as Word) as Address
     }
 
-    fn fetch_immediate(&self) -> Address {
-        self.regs.pc
+    fn fetch_immediate(&self, operand: Address) -> Address {
+        operand
     }
 
-    fn fetch_indirect(&self) -> Address {
-        let addr = self.mem.read_word(self.regs.pc);
-        let lsb  = self.mem.read_byte(addr);
+    fn fetch_indirect(&self, mem: &impl Memory, operand: Address) -> Address {
+        let addr = mem.read_word(operand);
+        let lsb  = mem.read_byte(addr);
         // If addr == 0xXXFF (X is arbitrarily), msb will be fetched from 0xXX00
-        let msb  = self.mem.read_byte(if addr & 0xFF == 0xFF { addr & 0xFF00 } else { addr + 1 });
+        let msb  = mem.read_byte(if addr & 0xFF == 0xFF { addr & 0xFF00 } else { addr + 1 });
 
         Address::from_le_bytes([lsb, msb])
     }
 
-    fn fetch_indirect_with_index(&self, msb_index: Byte, lsb_index: Byte) -> Address {
-        let addr = self.mem.read_byte(self.regs.pc).wrapping_add(msb_index) as Address;
-        self.mem.read_word(addr).wrapping_add(lsb_index as Word) as Address
+    // The pointer never go beyond zero page, so if the pointer is 0xFF,
+    // msb will be fetched from 0x00.
+    fn fetch_indirect_with_index(&self, mem: &impl Memory, operand: Address, msb_index: Byte, lsb_index: Byte) -> Address {
+        let pointer = mem.read_byte(operand).wrapping_add(msb_index);
+        let lsb     = mem.read_byte(pointer as Address);
+        let msb     = mem.read_byte(pointer.wrapping_add(1) as Address);
+
+        Address::from_le_bytes([lsb, msb]).wrapping_add(lsb_index as Word)
     }
 
-    fn fetch_relative(&self) -> Address {
-        let offset = self.mem.read_byte(self.regs.pc);
+    fn fetch_relative(&self, mem: &impl Memory, operand: Address) -> Address {
+        let offset = mem.read_byte(operand);
         if offset >> 7 == 1 {
-            self.regs.pc.wrapping_add(1).wrapping_sub((!offset).wrapping_add(1) as Address)
+            operand.wrapping_add(1).wrapping_sub((!offset).wrapping_add(1) as Address)
         } else {
-            self.regs.pc.wrapping_add(1).wrapping_add(offset as Address)
+            operand.wrapping_add(1).wrapping_add(offset as Address)
         }
     }
 
-    fn fetch_zero_page_with_index(&self, index: Byte) -> Address {
-        self.mem.read_byte(self.regs.pc).wrapping_add(index) as Address
+    fn fetch_zero_page_with_index(&self, mem: &impl Memory, operand: Address, index: Byte) -> Address {
+        mem.read_byte(operand).wrapping_add(index) as Address
     }
 }
 
-impl Cpu {
-    pub fn new(mem: Box<dyn Memory>) -> Self {
-        Self { regs: Register::new(), mem }
+impl<M: Memory> Cpu<M> {
+    pub fn new(mem: M) -> Self {
+        Self::with_variant(mem, CpuVariant::default())
+    }
+
+    pub fn with_variant(mem: M, variant: CpuVariant) -> Self {
+        Self {
+            regs: Register::new(),
+            mem,
+            variant,
+            cycles: 0,
+            nmi_line: false,
+            nmi_pending: false,
+            irq_line: false,
+            waiting: false,
+            tracer: None,
+        }
+    }
+
+    /// Set tracer which receive a line per instruction in nestest.log format.
+    ///
+    /// If `None` is supplied, tracing is disabled and no line is formatted.
+    pub fn set_tracer(&mut self, tracer: Option<Box<dyn Tracer>>) {
+        self.tracer = tracer;
+    }
+
+    /// Return the accumulator.
+    pub fn a(&self) -> Byte {
+        self.regs.a
+    }
+
+    /// Return the index register x.
+    pub fn x(&self) -> Byte {
+        self.regs.x
+    }
+
+    /// Return the index register y.
+    pub fn y(&self) -> Byte {
+        self.regs.y
+    }
+
+    /// Return the program counter.
+    pub fn pc(&self) -> Address {
+        self.regs.pc
+    }
+
+    /// Return the stack pointer.
+    pub fn s(&self) -> Byte {
+        self.regs.s
+    }
+
+    /// Return the processor status as a byte.
+    pub fn p(&self) -> Byte {
+        self.regs.p.bits()
+    }
+
+    /// Set the accumulator.
+    pub fn set_a(&mut self, value: Byte) {
+        self.regs.a = value;
     }
 
-    fn power_on(&mut self) {
+    /// Set the index register x.
+    pub fn set_x(&mut self, value: Byte) {
+        self.regs.x = value;
+    }
+
+    /// Set the index register y.
+    pub fn set_y(&mut self, value: Byte) {
+        self.regs.y = value;
+    }
+
+    /// Set the program counter.
+    pub fn set_pc(&mut self, value: Address) {
+        self.regs.pc = value;
+    }
+
+    /// Set the stack pointer.
+    pub fn set_s(&mut self, value: Byte) {
+        self.regs.s = value;
+    }
+
+    /// Set the processor status from a byte.
+    pub fn set_p(&mut self, value: Byte) {
+        self.regs.p = Status::from_bits_truncate(value);
+    }
+
+    /// Return the variant the cpu behave as.
+    pub fn variant(&self) -> CpuVariant {
+        self.variant
+    }
+
+    /// Return the number of cycles the cpu has consumed since power on.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Set whether nmi line is asserted or not.
+    ///
+    /// Nmi is edge-triggered, so it is requested only when the line become asserted.
+    /// The request is kept until the cpu service it.
+    pub fn set_nmi_line(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = asserted;
+    }
+
+    /// Set whether irq line is asserted or not.
+    ///
+    /// Irq is level-triggered, so it is requested while the line is asserted
+    /// and interrupt flag is cleared.
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    /// Initialize registers as the cpu is powered on, and jump to reset vector.
+    pub fn power_on(&mut self) {
         self.regs.a  = 0x00;
         self.regs.x  = 0x00;
         self.regs.y  = 0x00;
@@ -104,88 +277,320 @@ impl Cpu {
         self.regs.s  = 0xFD;
         self.regs.p.remove(Status::all());
         self.regs.p.insert(Status::INTERRUPT | Status::BREAK1 | Status::BREAK2);
+        self.cycles = 7;
     }
 
-    fn reset(&mut self) {
+    /// Reset the cpu, and jump to reset vector.
+    pub fn reset(&mut self) {
+        self.nmi_pending = false;
+        self.waiting     = false;
         self.regs.pc = self.mem.read_word(0xFFFC);
         self.regs.s  = self.regs.s.wrapping_sub(3);
         self.regs.p.insert(Status::INTERRUPT);
-    }
+        self.cycles += 7;
+    }
+
+    /// Service a requested interrupt if exist, then fetch one instruction and execute it.
+    ///
+    /// If the instruction can't be executed, the program counter is left at the
+    /// opcode and an error is returned. Memory is ticked with consumed cycles in
+    /// both cases, so cycles of a serviced interrupt are never lost.
+    pub fn step(&mut self) -> Result<StepInfo, CpuError> {
+        let start     = self.cycles;
+        let interrupt = self.poll_interrupt();
+
+        // Wai resumes by irq even if it is masked
+        if self.waiting {
+            if interrupt.is_some() || self.irq_line {
+                self.waiting = false;
+            } else {
+                self.cycles += 1;
+                self.mem.tick(self.cycles - start);
+                return Ok(StepInfo {
+                    opcode: 0xCB,
+                    name: Mnemonic::Wai,
+                    mode: AddressingMode::Implied,
+                    cycles: self.cycles - start,
+                    interrupt,
+                });
+            }
+        }
+
+        if let Some(mut tracer) = self.tracer.take() {
+            tracer.trace(&self.trace_line());
+            self.tracer = Some(tracer);
+        }
+
+        let result = self.step_instruction();
+        if self.cycles != start {
+            self.mem.tick(self.cycles - start);
+        }
 
-    fn run(&mut self) {
-        loop {
-            let opcode       = self.fetch_opcode();
-            let info = OPCODE_TABLE.get(&opcode).unwrap_or_else(|| panic!("No such opcode: 0x{:x}", opcode));
-            let (addr, name) = self.fetch_address(info); 
-
-            match info.name {
-                Mnemonic::Adc => self.adc(addr),
-                Mnemonic::And => self.and(addr),
-                Mnemonic::Asl if *name == AddressingMode::Accumulator => self.asl_acc(),
-                Mnemonic::Asl if *name != AddressingMode::Implied     => self.asl(addr),
-                Mnemonic::Bcc => self.bcc(addr),
-                Mnemonic::Bcs => self.bcs(addr),
-                Mnemonic::Beq => self.beq(addr),
-                Mnemonic::Bit => self.bit(addr),
-                Mnemonic::Bmi => self.bmi(addr),
-                Mnemonic::Bne => self.bne(addr),
-                Mnemonic::Bpl => self.bpl(addr),
-                Mnemonic::Brk => return,
-                Mnemonic::Bvc => self.bvc(addr),
-                Mnemonic::Bvs => self.bvs(addr),
-                Mnemonic::Clc if *name == AddressingMode::Implied => self.clc(),
-                Mnemonic::Cld if *name == AddressingMode::Implied => self.cld(),
-                Mnemonic::Cli if *name == AddressingMode::Implied => self.cli(),
-                Mnemonic::Clv if *name == AddressingMode::Implied => self.clv(),
-                Mnemonic::Cmp => self.cmp(addr),
-                Mnemonic::Cpx => self.cpx(addr),
-                Mnemonic::Cpy => self.cpy(addr),
-                Mnemonic::Dec => self.dec(addr),
-                Mnemonic::Dex if *name == AddressingMode::Implied => self.dex(),
-                Mnemonic::Dey if *name == AddressingMode::Implied => self.dey(),
-                Mnemonic::Eor => self.eor(addr),
-                Mnemonic::Inc => self.inc(addr),
-                Mnemonic::Inx if *name == AddressingMode::Implied => self.inx(),
-                Mnemonic::Iny if *name == AddressingMode::Implied => self.iny(),
-                Mnemonic::Jmp => self.jmp(addr),
-                Mnemonic::Jsr => todo!("Jsr is not implemented"),
-                Mnemonic::Lda => self.lda(addr),
-                Mnemonic::Ldx => self.ldx(addr),
-                Mnemonic::Ldy => self.ldy(addr),
-                Mnemonic::Lsr if *name == AddressingMode::Accumulator => self.lsr_acc(),
-                Mnemonic::Lsr if *name != AddressingMode::Implied     => self.lsr(addr),
-                Mnemonic::Nop => (), // Nothing happen, so there is no function
-                Mnemonic::Ora => self.ora(addr),
-                Mnemonic::Pha if *name == AddressingMode::Implied => self.pha(),
-                Mnemonic::Php if *name == AddressingMode::Implied => self.php(),
-                Mnemonic::Pla if *name == AddressingMode::Implied => self.pla(),
-                Mnemonic::Plp if *name == AddressingMode::Implied => self.plp(),
-                Mnemonic::Rol if *name == AddressingMode::Accumulator => self.rol_acc(),
-                Mnemonic::Rol if *name != AddressingMode::Implied     => self.rol(addr),
-                Mnemonic::Ror if *name == AddressingMode::Accumulator => self.ror_acc(),
-                Mnemonic::Ror if *name != AddressingMode::Implied     => self.ror(addr),
-                Mnemonic::Rti if *name == AddressingMode::Implied => todo!("Rti is not implemented"),
-                Mnemonic::Rts if *name == AddressingMode::Implied => todo!("Rts is not implemented"),
-                Mnemonic::Sbc => self.sbc(addr),
-                Mnemonic::Sec if *name == AddressingMode::Implied => self.sec(),
-                Mnemonic::Sed if *name == AddressingMode::Implied => self.sed(),
-                Mnemonic::Sei if *name == AddressingMode::Implied => self.sei(),
-                Mnemonic::Sta => self.sta(addr),
-                Mnemonic::Stx => self.stx(addr),
-                Mnemonic::Sty => self.sty(addr),
-                Mnemonic::Tax if *name == AddressingMode::Implied => self.tax(),
-                Mnemonic::Tay if *name == AddressingMode::Implied => self.tay(),
-                Mnemonic::Tsx if *name == AddressingMode::Implied => self.tsx(),
-                Mnemonic::Txa if *name == AddressingMode::Implied => self.txa(),
-                Mnemonic::Txs if *name == AddressingMode::Implied => self.txs(),
-                Mnemonic::Tya if *name == AddressingMode::Implied => self.tya(),
-                _ => panic!("{:?} is not exist on {:?}", info.mode, info.name),
+        let (opcode, info) = result?;
+        Ok(StepInfo {
+            opcode,
+            name: info.name,
+            mode: info.mode,
+            cycles: self.cycles - start,
+            interrupt,
+        })
+    }
+
+    // Fetch one instruction and execute it, without ticking memory.
+    fn step_instruction(&mut self) -> Result<(Opcode, &'static OpcodeInfo), CpuError> {
+        let pc     = self.regs.pc;
+        let opcode = self.fetch_opcode();
+        let info   = match self.lookup_opcode(opcode) {
+            Some(info) => info,
+            None => {
+                self.regs.pc = pc;
+                return Err(CpuError::IllegalOpcode { pc, opcode });
+            }
+        };
+        let (addr, _) = self.fetch_address(info);
+
+        // Index registers may be changed by the instruction, so check it before execution.
+        let penalty = if self.is_page_crossed(&info.mode, addr) { self.page_cross_penalty(&info.name) } else { 0 };
+
+        self.execute(pc, opcode, info, addr)?;
+        self.cycles += info.cycle as u64 + penalty;
+
+        Ok((opcode, info))
+    }
+
+    // Execute the instruction whose operand is at `addr`. The program counter must
+    // point to next instruction, and it is restored to `pc` on error.
+    fn execute(&mut self, pc: Address, opcode: Opcode, info: &OpcodeInfo, addr: Address) -> Result<(), CpuError> {
+        let name = &info.mode;
+        match info.name {
+            Mnemonic::Adc => self.adc(addr),
+            Mnemonic::And => self.and(addr),
+            Mnemonic::Asl if *name == AddressingMode::Accumulator => self.asl_acc(),
+            Mnemonic::Asl if *name != AddressingMode::Implied     => self.asl(addr),
+            Mnemonic::Bcc => self.bcc(addr),
+            Mnemonic::Bcs => self.bcs(addr),
+            Mnemonic::Beq => self.beq(addr),
+            Mnemonic::Bit if *name == AddressingMode::Immediate => self.bit_imm(addr),
+            Mnemonic::Bit => self.bit(addr),
+            Mnemonic::Bmi => self.bmi(addr),
+            Mnemonic::Bne => self.bne(addr),
+            Mnemonic::Bpl => self.bpl(addr),
+            Mnemonic::Brk if *name == AddressingMode::Implied => self.brk(),
+            Mnemonic::Bvc => self.bvc(addr),
+            Mnemonic::Bvs => self.bvs(addr),
+            Mnemonic::Clc if *name == AddressingMode::Implied => self.clc(),
+            Mnemonic::Cld if *name == AddressingMode::Implied => self.cld(),
+            Mnemonic::Cli if *name == AddressingMode::Implied => self.cli(),
+            Mnemonic::Clv if *name == AddressingMode::Implied => self.clv(),
+            Mnemonic::Cmp => self.cmp(addr),
+            Mnemonic::Cpx => self.cpx(addr),
+            Mnemonic::Cpy => self.cpy(addr),
+            Mnemonic::Dec if *name == AddressingMode::Accumulator => self.dec_acc(),
+            Mnemonic::Dec => self.dec(addr),
+            Mnemonic::Dex if *name == AddressingMode::Implied => self.dex(),
+            Mnemonic::Dey if *name == AddressingMode::Implied => self.dey(),
+            Mnemonic::Eor => self.eor(addr),
+            Mnemonic::Inc if *name == AddressingMode::Accumulator => self.inc_acc(),
+            Mnemonic::Inc => self.inc(addr),
+            Mnemonic::Inx if *name == AddressingMode::Implied => self.inx(),
+            Mnemonic::Iny if *name == AddressingMode::Implied => self.iny(),
+            Mnemonic::Jmp => self.jmp(addr),
+            Mnemonic::Jsr => self.jsr(addr),
+            Mnemonic::Lda => self.lda(addr),
+            Mnemonic::Ldx => self.ldx(addr),
+            Mnemonic::Ldy => self.ldy(addr),
+            Mnemonic::Lsr if *name == AddressingMode::Accumulator => self.lsr_acc(),
+            Mnemonic::Lsr if *name != AddressingMode::Implied     => self.lsr(addr),
+            Mnemonic::Nop => (), // Nothing happen, so there is no function
+            Mnemonic::Ora => self.ora(addr),
+            Mnemonic::Pha if *name == AddressingMode::Implied => self.pha(),
+            Mnemonic::Php if *name == AddressingMode::Implied => self.php(),
+            Mnemonic::Pla if *name == AddressingMode::Implied => self.pla(),
+            Mnemonic::Plp if *name == AddressingMode::Implied => self.plp(),
+            Mnemonic::Rol if *name == AddressingMode::Accumulator => self.rol_acc(),
+            Mnemonic::Rol if *name != AddressingMode::Implied     => self.rol(addr),
+            Mnemonic::Ror if *name == AddressingMode::Accumulator => self.ror_acc(),
+            Mnemonic::Ror if *name != AddressingMode::Implied     => self.ror(addr),
+            Mnemonic::Rti if *name == AddressingMode::Implied => self.rti(),
+            Mnemonic::Rts if *name == AddressingMode::Implied => self.rts(),
+            Mnemonic::Sbc => self.sbc(addr),
+            Mnemonic::Sec if *name == AddressingMode::Implied => self.sec(),
+            Mnemonic::Sed if *name == AddressingMode::Implied => self.sed(),
+            Mnemonic::Sei if *name == AddressingMode::Implied => self.sei(),
+            Mnemonic::Sta => self.sta(addr),
+            Mnemonic::Stx => self.stx(addr),
+            Mnemonic::Sty => self.sty(addr),
+            Mnemonic::Tax if *name == AddressingMode::Implied => self.tax(),
+            Mnemonic::Tay if *name == AddressingMode::Implied => self.tay(),
+            Mnemonic::Tsx if *name == AddressingMode::Implied => self.tsx(),
+            Mnemonic::Txa if *name == AddressingMode::Implied => self.txa(),
+            Mnemonic::Txs if *name == AddressingMode::Implied => self.txs(),
+            Mnemonic::Tya if *name == AddressingMode::Implied => self.tya(),
+
+            // Unofficial mnemonics
+            Mnemonic::Ahx => self.ahx(addr),
+            Mnemonic::Alr => self.alr(addr),
+            Mnemonic::Anc => self.anc(addr),
+            Mnemonic::Arr => self.arr(addr),
+            Mnemonic::Axs => self.axs(addr),
+            Mnemonic::Dcp => self.dcp(addr),
+            Mnemonic::Isc => self.isc(addr),
+            Mnemonic::Kil => {
+                self.regs.pc = pc;
+                return Err(CpuError::Jammed { pc, opcode });
+            }
+            Mnemonic::Las => self.las(addr),
+            Mnemonic::Lax => self.lax(addr),
+            Mnemonic::Lxa => self.lxa(addr),
+            Mnemonic::Rla => self.rla(addr),
+            Mnemonic::Rra => self.rra(addr),
+            Mnemonic::Sax => self.sax(addr),
+            Mnemonic::Shx => self.shx(addr),
+            Mnemonic::Shy => self.shy(addr),
+            Mnemonic::Slo => self.slo(addr),
+            Mnemonic::Sre => self.sre(addr),
+            Mnemonic::Tas => self.tas(addr),
+            Mnemonic::Xaa => self.xaa(addr),
+
+            // 65C02 mnemonics
+            Mnemonic::Bra => self.bra(addr),
+            Mnemonic::Phx => self.phx(),
+            Mnemonic::Phy => self.phy(),
+            Mnemonic::Plx => self.plx(),
+            Mnemonic::Ply => self.ply(),
+            Mnemonic::Stp => {
+                self.regs.pc = pc;
+                return Err(CpuError::Jammed { pc, opcode });
             }
+            Mnemonic::Stz => self.stz(addr),
+            Mnemonic::Trb => self.trb(addr),
+            Mnemonic::Tsb => self.tsb(addr),
+            Mnemonic::Wai => self.wai(),
+            Mnemonic::Rmb0 => self.rmb(addr, 0),
+            Mnemonic::Rmb1 => self.rmb(addr, 1),
+            Mnemonic::Rmb2 => self.rmb(addr, 2),
+            Mnemonic::Rmb3 => self.rmb(addr, 3),
+            Mnemonic::Rmb4 => self.rmb(addr, 4),
+            Mnemonic::Rmb5 => self.rmb(addr, 5),
+            Mnemonic::Rmb6 => self.rmb(addr, 6),
+            Mnemonic::Rmb7 => self.rmb(addr, 7),
+            Mnemonic::Smb0 => self.smb(addr, 0),
+            Mnemonic::Smb1 => self.smb(addr, 1),
+            Mnemonic::Smb2 => self.smb(addr, 2),
+            Mnemonic::Smb3 => self.smb(addr, 3),
+            Mnemonic::Smb4 => self.smb(addr, 4),
+            Mnemonic::Smb5 => self.smb(addr, 5),
+            Mnemonic::Smb6 => self.smb(addr, 6),
+            Mnemonic::Smb7 => self.smb(addr, 7),
+            Mnemonic::Bbr0 => self.bbr(addr, 0),
+            Mnemonic::Bbr1 => self.bbr(addr, 1),
+            Mnemonic::Bbr2 => self.bbr(addr, 2),
+            Mnemonic::Bbr3 => self.bbr(addr, 3),
+            Mnemonic::Bbr4 => self.bbr(addr, 4),
+            Mnemonic::Bbr5 => self.bbr(addr, 5),
+            Mnemonic::Bbr6 => self.bbr(addr, 6),
+            Mnemonic::Bbr7 => self.bbr(addr, 7),
+            Mnemonic::Bbs0 => self.bbs(addr, 0),
+            Mnemonic::Bbs1 => self.bbs(addr, 1),
+            Mnemonic::Bbs2 => self.bbs(addr, 2),
+            Mnemonic::Bbs3 => self.bbs(addr, 3),
+            Mnemonic::Bbs4 => self.bbs(addr, 4),
+            Mnemonic::Bbs5 => self.bbs(addr, 5),
+            Mnemonic::Bbs6 => self.bbs(addr, 6),
+            Mnemonic::Bbs7 => self.bbs(addr, 7),
+            _ => {
+                self.regs.pc = pc;
+                return Err(CpuError::InvalidAddressingMode { pc, name: info.name, mode: info.mode });
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute instructions until at least `cycles` cycles are consumed.
+    ///
+    /// Because an instruction can't be stopped halfway, this may consume a few cycles
+    /// more than `cycles`. Return the number of cycles actually consumed.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> Result<u64, CpuError> {
+        let start = self.cycles;
+        while self.cycles - start < cycles {
+            self.step()?;
+        }
+        Ok(self.cycles - start)
+    }
+
+    /// Execute instructions until `predicate` return true.
+    ///
+    /// The predicate is checked before each instruction, so no instruction is
+    /// executed if it return true at first.
+    pub fn run_until<F>(&mut self, mut predicate: F) -> Result<(), CpuError>
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        while !predicate(self) {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    // Only instructions that just read memory take one more cycle when page is crossed.
+    // Stores and read-modify-write instructions always take the extra cycle, so it is
+    // already included in OPCODE_TABLE. On 65C02, shifts and rotates also take it.
+    fn page_cross_penalty(&self, name: &Mnemonic) -> u64 {
+        match name {
+            Mnemonic::Adc | Mnemonic::And | Mnemonic::Cmp |
+            Mnemonic::Eor | Mnemonic::Lda | Mnemonic::Ldx |
+            Mnemonic::Ldy | Mnemonic::Ora | Mnemonic::Sbc |
+            Mnemonic::Las | Mnemonic::Lax | Mnemonic::Nop |
+            Mnemonic::Bit => 1,
+            Mnemonic::Asl | Mnemonic::Lsr |
+            Mnemonic::Rol | Mnemonic::Ror if self.variant.is_cmos() => 1,
+            _ => 0,
+        }
+    }
+
+    fn lookup_opcode(&self, opcode: Opcode) -> Option<&'static OpcodeInfo> {
+        self.variant.lookup_opcode(opcode)
+    }
+
+    // Check whether indexed addressing crossed page boundary, by comparing
+    // the effective address with the base address before indexed.
+    fn is_page_crossed(&self, mode: &AddressingMode, addr: Address) -> bool {
+        let base = match mode {
+            AddressingMode::AbsoluteX => addr.wrapping_sub(self.regs.x as Address),
+            AddressingMode::AbsoluteY | AddressingMode::IndirectY => addr.wrapping_sub(self.regs.y as Address),
+            _ => return false,
+        };
+        base & 0xFF00 != addr & 0xFF00
+    }
+
+    // Nmi has priority over irq
+    fn poll_interrupt(&mut self) -> Option<Interrupt> {
+        let interrupt = if self.nmi_pending {
+            self.nmi_pending = false;
+            Interrupt::Nmi
+        } else if self.irq_line && !self.regs.p.contains(Status::INTERRUPT) {
+            Interrupt::Irq
+        } else {
+            return None;
+        };
+
+        // Same 7 cycles sequence as brk except that break flag is cleared in pushed status.
+        self.push_word(self.regs.pc);
+        self.push_byte(((self.regs.p - Status::BREAK1) | Status::BREAK2).bits());
+        self.regs.p.insert(Status::INTERRUPT);
+        if self.variant.is_cmos() {
+            self.regs.p.remove(Status::DECIMAL);
         }
+        self.regs.pc = self.mem.read_word(interrupt.vector());
+        self.cycles += 7;
+
+        Some(interrupt)
     }
 
+    // Taken branch takes one more cycle, and one more if it jumps to another page.
     fn branch(&mut self, addr: Address, success: bool) {
         if success {
+            self.cycles += if self.regs.pc & 0xFF00 == addr & 0xFF00 { 1 } else { 2 };
             self.regs.pc = addr;
         }
     }
@@ -200,33 +605,116 @@ impl Cpu {
         self.mem.read_byte(self.regs.s as Address + 0x0100)
     }
 
+    // The msb is pushed first, so the word is stored as little endian in the stack
     fn push_word(&mut self, value: Word) {
         let bytes = value.to_le_bytes();
-        self.push_byte(bytes[0]);
-        self.push_byte(bytes[1])
+        self.push_byte(bytes[1]);
+        self.push_byte(bytes[0])
     }
 
     fn pull_word(&mut self) -> Word {
-        let msb = self.pull_byte();
         let lsb = self.pull_byte();
-        Word::from_le_bytes([msb, lsb])
+        let msb = self.pull_byte();
+        Word::from_le_bytes([lsb, msb])
     }
 
-    fn adc(&mut self, addr: Address) {
-        let carry        = if self.regs.p.contains(Status::CARRY) { 1 } else { 0 };
-        let value_to_add = self.mem.read_byte(addr).overflowing_add(carry);
-        let result       = self.regs.a.overflowing_add(value_to_add.0);
-        let is_carry     = value_to_add.1 | result.1;
-        let is_overflow  = (self.regs.a >> 7) == (value_to_add.0 >> 7) &&
-                           (self.regs.a >> 7) != (result.0       >> 7);
+    // Add value and carry to accumulator. Sbc is same as adc with inverted value.
+    fn add_with_carry(&mut self, value: Byte) {
+        let carry       = if self.regs.p.contains(Status::CARRY) { 1 } else { 0 };
+        let result      = self.regs.a as Word + value as Word + carry;
+        let is_carry    = result > 0xFF;
+        // Overflow happen when both operands have same sign and result has another sign.
+        let is_overflow = (self.regs.a ^ result as Byte) & (value ^ result as Byte) & 0b1000_0000 != 0;
 
-        self.regs.a = result.0;
+        self.regs.a = result as Byte;
 
         self.regs.p.set(Status::CARRY, is_carry);
         self.regs.p.set(Status::OVERFLOW, is_overflow);
         self.regs.p.update_zero_and_negative(self.regs.a);
     }
 
+    // Break flag doesn't exist in the register, so it is ignored when status is pulled.
+    fn pull_status(&mut self) -> Status {
+        (Status::from_bits_truncate(self.pull_byte()) - Status::BREAK1) | Status::BREAK2
+    }
+
+    // Nmos 6502 computes negative and overflow flags from the result before
+    // high digit is adjusted, and zero flag from the binary result.
+    // 65C02 computes negative and zero flags from the decimal result.
+    fn add_with_carry_decimal(&mut self, value: Byte) {
+        let carry  = if self.regs.p.contains(Status::CARRY) { 1 } else { 0 };
+        let binary = self.regs.a.wrapping_add(value).wrapping_add(carry as Byte);
+
+        let mut low = (self.regs.a & 0x0F) as i16 + (value & 0x0F) as i16 + carry;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let signed     = (self.regs.a & 0xF0) as i8 as i16 + (value & 0xF0) as i8 as i16 + low;
+        let mut result = (self.regs.a & 0xF0) as i16 + (value & 0xF0) as i16 + low;
+        if result >= 0xA0 {
+            result += 0x60;
+        }
+
+        self.regs.a = result as Byte;
+
+        self.regs.p.set(Status::CARRY, result >= 0x100);
+        self.regs.p.set(Status::OVERFLOW, !(-128..=127).contains(&signed));
+        self.regs.p.set(Status::NEGATIVE, signed & 0x80 != 0);
+        self.regs.p.set(Status::ZERO, binary == 0);
+        if self.variant.is_cmos() {
+            self.regs.p.update_zero_and_negative(self.regs.a);
+        }
+    }
+
+    // Nmos 6502 set all flags same as binary mode.
+    // 65C02 computes negative and zero flags from the decimal result.
+    fn sub_with_borrow_decimal(&mut self, value: Byte) {
+        let carry = if self.regs.p.contains(Status::CARRY) { 1 } else { 0 };
+
+        if self.variant.is_cmos() {
+            let low        = (self.regs.a & 0x0F) as i16 - (value & 0x0F) as i16 + carry - 1;
+            let mut result = self.regs.a as i16 - value as i16 + carry - 1;
+            if result < 0 {
+                result -= 0x60;
+            }
+            if low < 0 {
+                result -= 0x06;
+            }
+
+            self.add_with_carry(!value);
+            self.regs.a = result as Byte;
+            self.regs.p.update_zero_and_negative(self.regs.a);
+            return;
+        }
+
+        let mut low = (self.regs.a & 0x0F) as i16 - (value & 0x0F) as i16 + carry - 1;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0F) - 0x10;
+        }
+        let mut result = (self.regs.a & 0xF0) as i16 - (value & 0xF0) as i16 + low;
+        if result < 0 {
+            result -= 0x60;
+        }
+
+        self.add_with_carry(!value);
+        self.regs.a = result as Byte;
+    }
+
+    fn is_decimal_mode(&self) -> bool {
+        self.variant.has_decimal_mode() && self.regs.p.contains(Status::DECIMAL)
+    }
+
+    // 65C02 takes one more cycle in decimal mode.
+    fn adc(&mut self, addr: Address) {
+        let value = self.mem.read_byte(addr);
+        if self.is_decimal_mode() {
+            self.cycles += if self.variant.is_cmos() { 1 } else { 0 };
+            self.add_with_carry_decimal(value);
+        } else {
+            self.add_with_carry(value);
+        }
+    }
+
     fn and(&mut self, addr: Address) {
         self.regs.a &= self.mem.read_byte(addr);
         self.regs.p.update_zero_and_negative(self.regs.a);
@@ -263,7 +751,7 @@ impl Cpu {
     fn bit(&mut self, addr: Address) {
         self.regs.p.set(Status::NEGATIVE, self.mem.read_byte(addr) & 0b1000_0000 != 0);
         self.regs.p.set(Status::OVERFLOW, self.mem.read_byte(addr) & 0b0100_0000 != 0);
-        self.regs.p.set(Status::ZERO,     self.mem.read_byte(addr) & self.regs.a != 0);
+        self.regs.p.set(Status::ZERO,     self.mem.read_byte(addr) & self.regs.a == 0);
     }
 
     fn bmi(&mut self, addr: Address) {
@@ -278,6 +766,17 @@ impl Cpu {
         self.branch(addr, !self.regs.p.contains(Status::NEGATIVE));
     }
 
+    // Brk has a padding byte after its opcode, so the pushed address skips it.
+    fn brk(&mut self) {
+        self.push_word(self.regs.pc.wrapping_add(1));
+        self.push_byte((self.regs.p | Status::BREAK1 | Status::BREAK2).bits());
+        self.regs.p.insert(Status::INTERRUPT);
+        if self.variant.is_cmos() {
+            self.regs.p.remove(Status::DECIMAL);
+        }
+        self.regs.pc = self.mem.read_word(Interrupt::Irq.vector());
+    }
+
     fn bvc(&mut self, addr: Address) {
         self.branch(addr, !self.regs.p.contains(Status::OVERFLOW));
     }
@@ -343,7 +842,7 @@ impl Cpu {
     }
 
     fn eor(&mut self, addr: Address) {
-        self.regs.a = self.regs.a ^ self.mem.read_byte(addr);
+        self.regs.a ^= self.mem.read_byte(addr);
 
         self.regs.p.update_zero_and_negative(self.regs.a);
     }
@@ -371,6 +870,12 @@ impl Cpu {
         self.regs.pc = addr;
     }
 
+    // The pushed address is the last byte of jsr instruction, not next instruction.
+    fn jsr(&mut self, addr: Address) {
+        self.push_word(self.regs.pc.wrapping_sub(1));
+        self.regs.pc = addr;
+    }
+
     fn lda(&mut self, addr: Address) {
         self.regs.a = self.mem.read_byte(addr);
 
@@ -415,8 +920,9 @@ impl Cpu {
         self.push_byte(self.regs.a);
     }
 
+    // Break flag is always set in pushed status, like brk.
     fn php(&mut self) {
-        self.push_byte(self.regs.p.bits());
+        self.push_byte((self.regs.p | Status::BREAK1 | Status::BREAK2).bits());
     }
 
     fn pla(&mut self) {
@@ -426,7 +932,7 @@ impl Cpu {
     }
 
     fn plp(&mut self) {
-        self.regs.p = Status::from_bits_truncate(self.pull_byte());
+        self.regs.p = self.pull_status();
     }
 
     fn rol_acc(&mut self) {
@@ -457,7 +963,7 @@ impl Cpu {
     }
 
     fn ror(&mut self, addr: Address) {
-        let is_carry = self.regs.a & 0b0000_0001 == 0b0000_0001;
+        let is_carry = self.mem.read_byte(addr) & 0b0000_0001 == 0b0000_0001;
         let carry    = if self.regs.p.contains(Status::CARRY) { 0b1000_0000 } else { 0 };
         self.mem.write_byte(addr, (self.mem.read_byte(addr) >> 1) + carry);
 
@@ -465,19 +971,23 @@ impl Cpu {
         self.regs.p.update_zero_and_negative(self.mem.read_byte(addr));
     }
 
-    fn sbc(&mut self, addr: Address) {
-        let carry        = if self.regs.p.contains(Status::CARRY) { 0 } else { 1 };
-        let value_to_sub = self.mem.read_byte(addr).overflowing_add(carry);
-        let result       = self.regs.a.overflowing_sub(value_to_sub.0);
-        let is_carry     = !(value_to_sub.1 | result.1);
-        let is_overflow  = (self.regs.a >> 7) == (value_to_sub.0 >> 7) &&
-                           (self.regs.a >> 7) != (result.0       >> 7);
+    fn rti(&mut self) {
+        self.regs.p  = self.pull_status();
+        self.regs.pc = self.pull_word();
+    }
 
-        self.regs.a = result.0;
+    fn rts(&mut self) {
+        self.regs.pc = self.pull_word().wrapping_add(1);
+    }
 
-        self.regs.p.set(Status::CARRY, is_carry);
-        self.regs.p.set(Status::OVERFLOW, is_overflow);
-        self.regs.p.update_zero_and_negative(self.regs.a);
+    fn sbc(&mut self, addr: Address) {
+        let value = self.mem.read_byte(addr);
+        if self.is_decimal_mode() {
+            self.cycles += if self.variant.is_cmos() { 1 } else { 0 };
+            self.sub_with_borrow_decimal(value);
+        } else {
+            self.add_with_carry(!value);
+        }
     }
 
     fn sec(&mut self) {
@@ -539,13 +1049,219 @@ impl Cpu {
     }
 }
 
+// Unofficial instructions.
+// Most of them are combination of two official instructions, so these are
+// implemented with official one as possible.
+impl<M: Memory> Cpu<M> {
+    fn ahx(&mut self, addr: Address) {
+        self.store_and_high(addr, self.regs.a & self.regs.x, self.regs.y);
+    }
+
+    fn alr(&mut self, addr: Address) {
+        self.and(addr);
+        self.lsr_acc();
+    }
+
+    fn anc(&mut self, addr: Address) {
+        self.and(addr);
+
+        self.regs.p.set(Status::CARRY, self.regs.p.contains(Status::NEGATIVE));
+    }
+
+    fn arr(&mut self, addr: Address) {
+        self.and(addr);
+        self.ror_acc();
+
+        self.regs.p.set(Status::CARRY,    self.regs.a & 0b0100_0000 != 0);
+        self.regs.p.set(Status::OVERFLOW, ((self.regs.a >> 6) ^ (self.regs.a >> 5)) & 1 == 1);
+    }
+
+    fn axs(&mut self, addr: Address) {
+        let result = (self.regs.a & self.regs.x).overflowing_sub(self.mem.read_byte(addr));
+        self.regs.x = result.0;
+
+        self.regs.p.set(Status::CARRY, !result.1);
+        self.regs.p.update_zero_and_negative(self.regs.x);
+    }
+
+    fn dcp(&mut self, addr: Address) {
+        self.dec(addr);
+        self.cmp(addr);
+    }
+
+    fn isc(&mut self, addr: Address) {
+        self.inc(addr);
+        self.sbc(addr);
+    }
+
+    fn las(&mut self, addr: Address) {
+        let result  = self.mem.read_byte(addr) & self.regs.s;
+        self.regs.a = result;
+        self.regs.x = result;
+        self.regs.s = result;
+
+        self.regs.p.update_zero_and_negative(result);
+    }
+
+    fn lax(&mut self, addr: Address) {
+        self.lda(addr);
+        self.regs.x = self.regs.a;
+    }
+
+    // Unstable. 0xEE is used as the magic constant.
+    fn lxa(&mut self, addr: Address) {
+        self.regs.a = (self.regs.a | 0xEE) & self.mem.read_byte(addr);
+        self.regs.x = self.regs.a;
+
+        self.regs.p.update_zero_and_negative(self.regs.a);
+    }
+
+    fn rla(&mut self, addr: Address) {
+        self.rol(addr);
+        self.and(addr);
+    }
+
+    fn rra(&mut self, addr: Address) {
+        self.ror(addr);
+        self.adc(addr);
+    }
+
+    fn sax(&mut self, addr: Address) {
+        self.mem.write_byte(addr, self.regs.a & self.regs.x);
+    }
+
+    fn shx(&mut self, addr: Address) {
+        self.store_and_high(addr, self.regs.x, self.regs.y);
+    }
+
+    fn shy(&mut self, addr: Address) {
+        self.store_and_high(addr, self.regs.y, self.regs.x);
+    }
+
+    fn slo(&mut self, addr: Address) {
+        self.asl(addr);
+        self.ora(addr);
+    }
+
+    fn sre(&mut self, addr: Address) {
+        self.lsr(addr);
+        self.eor(addr);
+    }
+
+    fn tas(&mut self, addr: Address) {
+        self.regs.s = self.regs.a & self.regs.x;
+        self.store_and_high(addr, self.regs.s, self.regs.y);
+    }
+
+    // Unstable. 0xEE is used as the magic constant.
+    fn xaa(&mut self, addr: Address) {
+        self.regs.a = (self.regs.a | 0xEE) & self.regs.x & self.mem.read_byte(addr);
+
+        self.regs.p.update_zero_and_negative(self.regs.a);
+    }
+
+    // Ahx, Shx, Shy and Tas store `value & (high byte of base address + 1)`.
+    // If indexing crosses page boundary, the high byte of address is replaced by the stored value.
+    fn store_and_high(&mut self, addr: Address, value: Byte, index: Byte) {
+        let base   = addr.wrapping_sub(index as Address);
+        let result = value & ((base >> 8) as Byte).wrapping_add(1);
+        let addr   = if base & 0xFF00 != addr & 0xFF00 {
+            Address::from_le_bytes([addr as Byte, result])
+        } else {
+            addr
+        };
+        self.mem.write_byte(addr, result);
+    }
+}
+
+// 65C02 instructions.
+impl<M: Memory> Cpu<M> {
+    fn bbr(&mut self, addr: Address, bit: u8) {
+        let target = self.fetch_relative(&self.mem, self.regs.pc.wrapping_sub(1));
+        self.branch(target, self.mem.read_byte(addr) & (1 << bit) == 0);
+    }
+
+    fn bbs(&mut self, addr: Address, bit: u8) {
+        let target = self.fetch_relative(&self.mem, self.regs.pc.wrapping_sub(1));
+        self.branch(target, self.mem.read_byte(addr) & (1 << bit) != 0);
+    }
+
+    // Bit with immediate only change zero flag.
+    fn bit_imm(&mut self, addr: Address) {
+        self.regs.p.set(Status::ZERO, self.mem.read_byte(addr) & self.regs.a == 0);
+    }
+
+    fn bra(&mut self, addr: Address) {
+        self.branch(addr, true);
+    }
+
+    fn dec_acc(&mut self) {
+        self.regs.a = self.regs.a.wrapping_sub(1);
+
+        self.regs.p.update_zero_and_negative(self.regs.a);
+    }
+
+    fn inc_acc(&mut self) {
+        self.regs.a = self.regs.a.wrapping_add(1);
+
+        self.regs.p.update_zero_and_negative(self.regs.a);
+    }
+
+    fn phx(&mut self) {
+        self.push_byte(self.regs.x);
+    }
+
+    fn phy(&mut self) {
+        self.push_byte(self.regs.y);
+    }
+
+    fn plx(&mut self) {
+        self.regs.x = self.pull_byte();
+
+        self.regs.p.update_zero_and_negative(self.regs.x);
+    }
+
+    fn ply(&mut self) {
+        self.regs.y = self.pull_byte();
+
+        self.regs.p.update_zero_and_negative(self.regs.y);
+    }
+
+    fn rmb(&mut self, addr: Address, bit: u8) {
+        self.mem.write_byte(addr, self.mem.read_byte(addr) & !(1 << bit));
+    }
+
+    fn smb(&mut self, addr: Address, bit: u8) {
+        self.mem.write_byte(addr, self.mem.read_byte(addr) | (1 << bit));
+    }
+
+    fn stz(&mut self, addr: Address) {
+        self.mem.write_byte(addr, 0);
+    }
+
+    fn trb(&mut self, addr: Address) {
+        let value = self.mem.read_byte(addr);
+        self.mem.write_byte(addr, value & !self.regs.a);
+
+        self.regs.p.set(Status::ZERO, value & self.regs.a == 0);
+    }
+
+    fn tsb(&mut self, addr: Address) {
+        let value = self.mem.read_byte(addr);
+        self.mem.write_byte(addr, value | self.regs.a);
+
+        self.regs.p.set(Status::ZERO, value & self.regs.a == 0);
+    }
+
+    // The cpu stop until interrupt is requested. See step.
+    fn wai(&mut self) {
+        self.waiting = true;
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::fs::*;
-    use std::io::*;
     use super::*;
-    use crate::core::bus::Bus;
-    use crate::core::cartridge::Cartoridge;
 
     struct MyVec {
         vec: Vec<u8>,
@@ -553,7 +1269,7 @@ mod test {
 
     impl MyVec {
         fn new(program: Vec<u8>) -> Self {
-            let mut vec: Vec<u8> = vec![2; 0xFFFF];
+            let mut vec: Vec<u8> = vec![2; 0x10000];
             vec[0xFFFC] = 0x00;
             vec[0xFFFD] = 0x80;
             for (index, byte) in program.into_iter().enumerate() {
@@ -561,6 +1277,14 @@ mod test {
             }
             Self { vec }
         }
+
+        // Program is placed at 0x8000, unless source has `.org`.
+        fn from_asm(source: &str, variant: CpuVariant) -> Self {
+            let assembly = asm::assemble_with_variant(&format!(".org $8000\n{}", source), variant).unwrap();
+            let mut mem  = Self::new(Vec::new());
+            assembly.write_to(&mut mem);
+            mem
+        }
     }
 
     impl Memory for MyVec {
@@ -579,6 +1303,11 @@ mod test {
         }
     }
 
+    // Execute instructions until the cpu reach to brk. The brk itself is not executed.
+    fn run_until_brk(cpu: &mut Cpu<MyVec>) {
+        cpu.run_until(|cpu| cpu.mem.peek_byte(cpu.regs.pc) == 0x00).unwrap();
+    }
+
     #[test]
     fn test_update_negative_and_zero_is_working() {
         let mut status = Status::new();
@@ -590,11 +1319,23 @@ mod test {
 
     #[test]
     fn test_adc() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x69, 0xFF, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x69, 0xFF, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0x10;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x0F);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+    }
+
+    #[test]
+    fn test_adc_asm() {
+        let mut cpu = Cpu::new(MyVec::from_asm("ADC #$FF\nBRK", CpuVariant::default()));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x0F);
         assert!(cpu.regs.p.contains(Status::CARRY));
@@ -602,73 +1343,137 @@ mod test {
 
     #[test]
     fn test_adc_overflow() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x69, 0x80, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x69, 0x80, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x80;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x00);
         assert!(cpu.regs.p.contains(Status::CARRY & Status::OVERFLOW & Status::ZERO));
     }
 
     #[test]
-    fn test_and() {
-        let mut mem = MyVec::new(vec![0x25, 0x00, 0x00]);
-        mem.vec[0] = 0x10;
-
-        let mut cpu = Cpu::new(Box::new(mem));
+    fn test_adc_overflow_by_carry() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x69, 0x7F, 0x00]));
         cpu.power_on();
-        cpu.regs.a = 0xFF;
+        cpu.regs.a = 0x00;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        cpu.regs.p.insert(Status::CARRY);
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.a, 0x10);
-        assert!(cpu.regs.p.is_empty());
+        assert_eq!(cpu.regs.a, 0x80);
+        assert_eq!(cpu.regs.p, Status::OVERFLOW | Status::NEGATIVE);
     }
 
     #[test]
-    fn test_asl() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x0A, 0x00])));
+    fn test_adc_decimal() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x69, 0x46, 0x00]), CpuVariant::Nmos6502);
         cpu.power_on();
-        cpu.regs.a = 0xFF;
+        cpu.regs.a = 0x58;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        cpu.regs.p.insert(Status::DECIMAL | Status::CARRY);
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.a, 0xFF << 1);
-        assert_eq!(cpu.regs.p.bits(), Status::CARRY.bits() | Status::NEGATIVE.bits());
+        // Intermediate result 0xA5 set negative and overflow
+        assert_eq!(cpu.regs.a, 0x05);
+        assert_eq!(cpu.regs.p, Status::DECIMAL | Status::CARRY | Status::OVERFLOW | Status::NEGATIVE);
     }
 
     #[test]
-    fn test_bcc() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x90, 0x01, 0x00, 0x00])));
+    fn test_adc_decimal_nmos_flags() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x69, 0x01, 0x00]), CpuVariant::Nmos6502);
         cpu.power_on();
+        cpu.regs.a = 0x79;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        cpu.regs.p.insert(Status::DECIMAL);
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        // Negative and overflow are set from 0x80, which is the result before adjustment
+        assert_eq!(cpu.regs.a, 0x80);
+        assert_eq!(cpu.regs.p, Status::DECIMAL | Status::OVERFLOW | Status::NEGATIVE);
+    }
+
+    #[test]
+    fn test_adc_decimal_is_ignored_on_2a03() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x69, 0x01, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0x09;
+        cpu.regs.p.remove(Status::all());
+        cpu.regs.p.insert(Status::DECIMAL);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x0A);
+    }
+
+    #[test]
+    fn test_and() {
+        let mut mem = MyVec::new(vec![0x25, 0x00, 0x00]);
+        mem.vec[0] = 0x10;
+
+        let mut cpu = Cpu::new(mem);
+        cpu.power_on();
+        cpu.regs.a = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x10);
+        assert!(cpu.regs.p.is_empty());
+    }
+
+    #[test]
+    fn test_asl() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x0A, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0xFF << 1);
+        assert_eq!(cpu.regs.p.bits(), Status::CARRY.bits() | Status::NEGATIVE.bits());
+    }
+
+    #[test]
+    fn test_bcc() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x90, 0x01, 0x00, 0x00]));
+        cpu.power_on();
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
     fn test_bcs() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xB0, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB0, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::CARRY);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
     fn test_beq() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xF0, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xF0, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::ZERO);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
+    }
+
+    #[test]
+    fn test_beq_asm() {
+        let mut cpu = Cpu::new(MyVec::from_asm("BEQ end\nBRK\nend: BRK", CpuVariant::default()));
+        cpu.power_on();
+        cpu.regs.p.remove(Status::all());
+        cpu.regs.p.insert(Status::ZERO);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
@@ -676,147 +1481,290 @@ mod test {
         let mut mem = MyVec::new(vec![0x24, 0x00, 0x00]);
         mem.vec[0] = 0xC0;
 
-        let mut cpu = Cpu::new(Box::new(mem));
+        let mut cpu = Cpu::new(mem);
         cpu.power_on();
-        cpu.regs.a = 0xC0;
+        cpu.regs.a = 0x3F;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::NEGATIVE | Status::OVERFLOW | Status::ZERO));
     }
 
     #[test]
     fn test_bmi() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x30, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x30, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::NEGATIVE);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
     fn test_bne() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xD0, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xD0, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
+    }
+
+    #[test]
+    fn test_bne_asm() {
+        let mut cpu = Cpu::new(MyVec::from_asm("BNE end\nBRK\nend: BRK", CpuVariant::default()));
+        cpu.power_on();
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
     fn test_bpl() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x10, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x10, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
+    }
+
+    #[test]
+    fn test_brk() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFE, 0x9000);
+        cpu.regs.s = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        cpu.regs.p.insert(Status::CARRY);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x9000);
+        assert_eq!(cpu.regs.s, 0xFC);
+        assert_eq!(cpu.mem.read_byte(0x01FF), 0x80);
+        assert_eq!(cpu.mem.read_byte(0x01FE), 0x02);
+        assert_eq!(cpu.mem.read_byte(0x01FD), (Status::CARRY | Status::BREAK1 | Status::BREAK2).bits());
+        assert!(cpu.regs.p.contains(Status::INTERRUPT));
     }
 
     #[test]
     fn test_bvc() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x50, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x50, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
     fn test_bvs() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x70, 0x01, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x70, 0x01, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::OVERFLOW);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.regs.pc, 0x8003);
     }
 
     #[test]
     fn test_clc() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x18, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x18, 0x00]));
         cpu.power_on();
         cpu.regs.p.insert(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(!cpu.regs.p.contains(Status::CARRY));
     }
 
     #[test]
     fn test_cld() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xD8, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xD8, 0x00]));
         cpu.power_on();
         cpu.regs.p.insert(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(!cpu.regs.p.contains(Status::DECIMAL));
     }
 
     #[test]
     fn test_cli() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x58, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x58, 0x00]));
         cpu.power_on();
         cpu.regs.p.insert(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(!cpu.regs.p.contains(Status::INTERRUPT));
     }
 
     #[test]
     fn test_clv() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xB8, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB8, 0x00]));
         cpu.power_on();
         cpu.regs.p.insert(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(!cpu.regs.p.contains(Status::OVERFLOW));
     }
 
     #[test]
     fn test_cmp() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xC9, 0x10, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xC9, 0x10, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::ZERO & Status::CARRY));
     }
 
     #[test]
     fn test_cpx() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xE0, 0x10, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xE0, 0x10, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::ZERO & Status::CARRY));
     }
 
     #[test]
     fn test_cpy() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xC0, 0x10, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xC0, 0x10, 0x00]));
         cpu.power_on();
         cpu.regs.y = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::ZERO & Status::CARRY));
     }
 
+    #[test]
+    fn test_cycles() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA9, 0x00, 0x85, 0x00, 0x00]));
+        cpu.power_on();
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.cycles(), 7 + 2 + 3);
+    }
+
+    #[test]
+    fn test_cycles_page_crossed() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB1, 0x10, 0x91, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0x0010, 0x02FF);
+        cpu.regs.y = 0x01;
+        run_until_brk(&mut cpu);
+
+        // Store doesn't take penalty
+        assert_eq!(cpu.cycles(), 7 + (5 + 1) + 6);
+    }
+
+    #[test]
+    fn test_cycles_branch() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xD0, 0x00, 0xF0, 0x00, 0xD0, 0x80]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x7F86, 0x00);
+        cpu.regs.p.remove(Status::ZERO);
+        run_until_brk(&mut cpu);
+
+        // Taken, not taken, and taken with page crossing
+        assert_eq!(cpu.cycles(), 7 + (2 + 1) + 2 + (2 + 2));
+    }
+
+    #[test]
+    fn test_step() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFE, 0x9000);
+        cpu.regs.p.remove(Status::INTERRUPT);
+        cpu.set_irq_line(true);
+        cpu.mem.write_byte(0x9000, 0xA9);
+        let info = cpu.step().unwrap();
+
+        assert_eq!(info, StepInfo {
+            opcode: 0xA9,
+            name: Mnemonic::Lda,
+            mode: AddressingMode::Immediate,
+            cycles: 7 + 2,
+            interrupt: Some(Interrupt::Irq),
+        });
+    }
+
+    #[test]
+    fn test_run_for_cycles() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0xEA, 0xA5, 0x00, 0x00]));
+        cpu.power_on();
+
+        assert_eq!(cpu.run_for_cycles(3), Ok(4));
+        assert_eq!(cpu.regs.pc, 0x8002);
+        assert_eq!(cpu.run_for_cycles(1), Ok(3));
+        assert_eq!(cpu.regs.pc, 0x8004);
+    }
+
+    // Memory that count ticked cycles
+    struct TickCounter {
+        vec: Vec<u8>,
+        ticks: Vec<u64>,
+    }
+
+    impl Memory for TickCounter {
+        fn read_byte(&self, addr: Address) -> Byte {
+            self.vec[addr as usize]
+        }
+
+        fn write_byte(&mut self, addr: Address, value: Byte) {
+            self.vec[addr as usize] = value;
+        }
+
+        fn tick(&mut self, cycles: u64) {
+            self.ticks.push(cycles);
+        }
+    }
+
+    #[test]
+    fn test_memory_tick() {
+        let mut vec = vec![0xEA; 0x10000];
+        vec[0x8001] = 0x02;
+        vec[0xFFFC] = 0x00;
+        vec[0xFFFD] = 0x80;
+        let mut cpu = Cpu::with_variant(TickCounter { vec, ticks: Vec::new() }, CpuVariant::OfficialOnly);
+        cpu.power_on();
+        cpu.set_nmi_line(true);
+        cpu.mem.vec[0xFFFA] = 0x00;
+        cpu.mem.vec[0xFFFB] = 0x80;
+
+        assert!(cpu.step().is_ok());
+        assert!(cpu.step().is_err());
+        // Nmi and Nop are ticked together, and failed instruction isn't ticked.
+        assert_eq!(cpu.mem.ticks, vec![7 + 2]);
+    }
+
+    #[test]
+    fn test_memory_tick_when_interrupt_handler_fails() {
+        let mut vec = vec![0x02; 0x10000];
+        vec[0xFFFA] = 0x00;
+        vec[0xFFFB] = 0x90;
+        let mut cpu = Cpu::with_variant(TickCounter { vec, ticks: Vec::new() }, CpuVariant::OfficialOnly);
+        cpu.set_nmi_line(true);
+
+        assert!(cpu.step().is_err());
+        assert_eq!(cpu.regs.pc, 0x9000);
+        // Nmi is serviced before the failed instruction, so its cycles are ticked.
+        assert_eq!(cpu.mem.ticks, vec![7]);
+    }
+
     #[test]
     fn test_dec() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xD6, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xD6, 0x00, 0x00]));
         cpu.power_on();
         cpu.mem.write_byte(0x0010, 1);
         cpu.regs.x = 0x10;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.mem.read_byte(0x0010), 0);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -824,10 +1772,10 @@ mod test {
 
     #[test]
     fn test_dex() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xCA, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xCA, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0x01;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.x, 0);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -835,10 +1783,10 @@ mod test {
 
     #[test]
     fn test_dey() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x88, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x88, 0x00]));
         cpu.power_on();
         cpu.regs.y = 0x01;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.y, 0);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -846,26 +1794,45 @@ mod test {
 
     #[test]
     fn test_eor() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x51, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x51, 0x00, 0x00]));
         cpu.power_on();
         cpu.mem.write_word(0x0000, 0x0020);
         cpu.mem.write_byte(0x0022, 0x10);
         cpu.regs.a = 0x30;
         cpu.regs.y = 0x02;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x20);
         assert!(cpu.regs.p.is_empty());
     }
 
+    #[test]
+    fn test_illegal_opcode() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xEA, 0x02]), CpuVariant::OfficialOnly);
+        cpu.power_on();
+
+        assert_eq!(cpu.run_for_cycles(4), Err(CpuError::IllegalOpcode { pc: 0x8001, opcode: 0x02 }));
+        assert_eq!(cpu.regs.pc, 0x8001);
+        assert_eq!(cpu.cycles(), 7 + 2);
+    }
+
+    #[test]
+    fn test_jammed() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0x02]));
+        cpu.power_on();
+
+        assert_eq!(cpu.run_for_cycles(4), Err(CpuError::Jammed { pc: 0x8001, opcode: 0x02 }));
+        assert_eq!(cpu.step(), Err(CpuError::Jammed { pc: 0x8001, opcode: 0x02 }));
+    }
+
     #[test]
     fn test_inc() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xEE, 0x00, 0x02, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEE, 0x00, 0x02, 0x00]));
         cpu.power_on();
         cpu.mem.write_byte(0x0200, 0xFF);
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.mem.read_byte(0x0200), 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -873,11 +1840,11 @@ mod test {
 
     #[test]
     fn test_inx() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xE8, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xE8, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.x, 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -885,30 +1852,60 @@ mod test {
 
     #[test]
     fn test_iny() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xC8, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xC8, 0x00]));
         cpu.power_on();
         cpu.regs.y = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.y, 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
     }
 
+    #[test]
+    fn test_irq() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFE, 0x9000);
+        cpu.mem.write_byte(0x9000, 0xEA);
+        cpu.regs.s = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        cpu.set_irq_line(true);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x9001);
+        assert_eq!(cpu.regs.s, 0xFC);
+        assert_eq!(cpu.mem.read_word(0x01FE), 0x8000);
+        assert_eq!(cpu.mem.read_byte(0x01FD), Status::BREAK2.bits());
+        assert!(cpu.regs.p.contains(Status::INTERRUPT));
+    }
+
+    #[test]
+    fn test_irq_is_masked() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFE, 0x9000);
+        cpu.regs.p.insert(Status::INTERRUPT);
+        cpu.set_irq_line(true);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x8001);
+    }
+
     #[test]
     fn test_jmp() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x6C, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x6C, 0x00, 0x00]));
         cpu.power_on();
         cpu.mem.write_word(0x0000, 0x0200);
         cpu.mem.write_byte(0x0200, 0x00);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.regs.pc, 0x0201);
+        assert_eq!(cpu.regs.pc, 0x0200);
     }
 
     #[test]
     fn test_jmp_indirect_error() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x6C, 0xFF, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x6C, 0xFF, 0x00]));
         cpu.power_on();
 
         cpu.mem.write_byte(0x0000, 0x02);
@@ -917,18 +1914,31 @@ mod test {
 
         cpu.mem.write_byte(0x0200, 0x00);
         cpu.mem.write_byte(0x0300, 0x00);
-        cpu.run();
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x0200);
+    }
 
-        assert_eq!(cpu.regs.pc, 0x0201);
+    #[test]
+    fn test_jsr() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x20, 0x00, 0x90]));
+        cpu.power_on();
+        cpu.regs.s = 0xFF;
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x9000);
+        assert_eq!(cpu.regs.s, 0xFD);
+        assert_eq!(cpu.mem.read_byte(0x01FF), 0x80);
+        assert_eq!(cpu.mem.read_byte(0x01FE), 0x02);
     }
 
     #[test]
     fn test_lda() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xA9, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA9, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -936,11 +1946,11 @@ mod test {
 
     #[test]
     fn test_ldx() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xA2, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA2, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.x, 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -948,11 +1958,11 @@ mod test {
 
     #[test]
     fn test_ldy() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xA0, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA0, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.y = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.y, 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -960,23 +1970,56 @@ mod test {
 
     #[test]
     fn test_lsr() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x4A, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x4A, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x08);
         assert!(cpu.regs.p.is_empty());
     }
 
+    #[test]
+    fn test_nmi() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFA, 0x9000);
+        cpu.mem.write_byte(0x9000, 0xEA);
+        cpu.regs.s = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        cpu.regs.p.insert(Status::INTERRUPT);
+        cpu.set_nmi_line(true);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x9001);
+        assert_eq!(cpu.regs.s, 0xFC);
+        assert_eq!(cpu.mem.read_word(0x01FE), 0x8000);
+        assert_eq!(cpu.mem.read_byte(0x01FD), (Status::INTERRUPT | Status::BREAK2).bits());
+    }
+
+    #[test]
+    fn test_nmi_is_edge_triggered() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFA, 0x9000);
+        cpu.mem.write_byte(0x9000, 0xEA);
+        cpu.mem.write_byte(0x9001, 0xEA);
+        cpu.set_nmi_line(true);
+        cpu.step().unwrap();
+        cpu.set_nmi_line(true);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x9002);
+    }
+
     #[test]
     fn test_ora() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x09, 0x81, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x09, 0x81, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x91);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
@@ -984,11 +2027,11 @@ mod test {
 
     #[test]
     fn test_pha() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x48, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x48, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.s = 0xFF;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.mem.read_byte(0x01FF), 0x10);
         assert_eq!(cpu.regs.s, 0xFE);
@@ -996,25 +2039,25 @@ mod test {
 
     #[test]
     fn test_php() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x08, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x08, 0x00]));
         let flags   = Status::CARRY & Status::ZERO & Status::NEGATIVE;
         cpu.power_on();
         cpu.regs.s = 0xFF;
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(flags);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
-        assert_eq!(cpu.mem.read_byte(0x01FF), flags.bits());
+        assert_eq!(cpu.mem.read_byte(0x01FF), (flags | Status::BREAK1 | Status::BREAK2).bits());
         assert_eq!(cpu.regs.s, 0xFE);
     }
 
     #[test]
     fn test_pla() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x68, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x68, 0x00]));
         cpu.power_on();
         cpu.mem.write_byte(0x01FF, 0x00);
         cpu.regs.s = 0xFE;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x00);
         assert!(cpu.regs.p.contains(Status::ZERO));
@@ -1022,24 +2065,24 @@ mod test {
 
     #[test]
     fn test_plp() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x28, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x28, 0x00]));
         cpu.power_on();
         cpu.mem.write_byte(0x01FF, 0xFF);
         cpu.regs.s = 0xFE;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.s, 0xFF);
-        assert!(cpu.regs.p.is_all());
+        assert_eq!(cpu.regs.p, Status::all() - Status::BREAK1);
     }
 
     #[test]
     fn test_rol() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x2A, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x2A, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0xFF;
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::CARRY);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0xFF);
         assert!(cpu.regs.p.contains(Status::CARRY & Status::NEGATIVE));
@@ -1047,109 +2090,177 @@ mod test {
 
     #[test]
     fn test_ror() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x6A, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x6A, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0xFF;
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::CARRY);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0xFF);
         assert!(cpu.regs.p.contains(Status::CARRY & Status::NEGATIVE));
     }
 
+    // Carry comes from the memory operand, not from the accumulator.
+    #[test]
+    fn test_ror_memory() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x66, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x01);
+        cpu.regs.a = 0x00;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x00);
+        assert!(cpu.regs.p.contains(Status::CARRY | Status::ZERO));
+    }
+
+    #[test]
+    fn test_rti() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x40]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x01FD, Status::CARRY.bits());
+        cpu.mem.write_word(0x01FE, 0x1234);
+        cpu.regs.s = 0xFC;
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x1234);
+        assert_eq!(cpu.regs.s, 0xFF);
+        assert_eq!(cpu.regs.p, Status::CARRY | Status::BREAK2);
+    }
+
+    #[test]
+    fn test_rts() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x20, 0x04, 0x80, 0x00, 0x60]));
+        cpu.power_on();
+        cpu.regs.s = 0xFF;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8003);
+        assert_eq!(cpu.regs.s, 0xFF);
+    }
+
+    #[test]
+    fn test_brk_and_rti() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x00, 0xFF, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0xFFFE, 0x9000);
+        cpu.mem.write_byte(0x9000, 0x40);
+        cpu.regs.p.remove(Status::all());
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x8002);
+        assert_eq!(cpu.regs.s, 0xFD);
+        assert_eq!(cpu.regs.p, Status::BREAK2);
+    }
+
     #[test]
     fn test_sbc() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xE9, 0x10, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xE9, 0x10, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0xFF);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
     }
 
+    #[test]
+    fn test_sbc_decimal() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xE9, 0x29, 0x00]), CpuVariant::Nmos6502);
+        cpu.power_on();
+        cpu.regs.a = 0x12;
+        cpu.regs.p.remove(Status::all());
+        cpu.regs.p.insert(Status::DECIMAL | Status::CARRY);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x83);
+        assert_eq!(cpu.regs.p, Status::DECIMAL | Status::NEGATIVE);
+    }
+
     #[test]
     fn test_sbc_overflow() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xE9, 0x70, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xE9, 0x70, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x80;
         cpu.regs.p.remove(Status::all());
         cpu.regs.p.insert(Status::CARRY);
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0x10);
         assert!(cpu.regs.p.contains(Status::CARRY & Status::OVERFLOW));
+        assert_eq!(cpu.regs.p, Status::CARRY | Status::OVERFLOW);
     }
 
     #[test]
     fn test_sec() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x38, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x38, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::CARRY));
     }
 
     #[test]
     fn test_sed() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xF8, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xF8, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::DECIMAL));
     }
 
     #[test]
     fn test_sei() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x78, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x78, 0x00]));
         cpu.power_on();
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert!(cpu.regs.p.contains(Status::INTERRUPT));
     }
 
     #[test]
     fn test_sta() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x85, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x85, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0x10;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.mem.read_byte(0x0000), 0x10);
     }
 
     #[test]
     fn test_stx() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x86, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x86, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0x10;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.mem.read_byte(0x0000), 0x10);
     }
 
     #[test]
     fn test_sty() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x84, 0x00, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x84, 0x00, 0x00]));
         cpu.power_on();
         cpu.regs.y = 0x10;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.mem.read_byte(0x0000), 0x10);
     }
 
     #[test]
     fn test_tax() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xAA, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xAA, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.x, 0xFF);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
@@ -1157,11 +2268,11 @@ mod test {
 
     #[test]
     fn test_tay() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xA8, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA8, 0x00]));
         cpu.power_on();
         cpu.regs.a = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.y, 0xFF);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
@@ -1169,11 +2280,11 @@ mod test {
 
     #[test]
     fn test_tsx() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0xBA, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0xBA, 0x00]));
         cpu.power_on();
         cpu.regs.s = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.x, 0xFF);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
@@ -1181,11 +2292,11 @@ mod test {
 
     #[test]
     fn test_txa() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x8A, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x8A, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0xFF);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
@@ -1193,23 +2304,603 @@ mod test {
 
     #[test]
     fn test_txs() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x9A, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x9A, 0x00]));
         cpu.power_on();
         cpu.regs.x = 0xFF;
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.s, 0xFF);
     }
 
     #[test]
     fn test_tya() {
-        let mut cpu = Cpu::new(Box::new(MyVec::new(vec![0x98, 0x00])));
+        let mut cpu = Cpu::new(MyVec::new(vec![0x98, 0x00]));
         cpu.power_on();
         cpu.regs.y = 0xFF;
         cpu.regs.p.remove(Status::all());
-        cpu.run();
+        run_until_brk(&mut cpu);
 
         assert_eq!(cpu.regs.a, 0xFF);
         assert!(cpu.regs.p.contains(Status::NEGATIVE));
     }
+
+    #[test]
+    fn test_alr() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x4B, 0x03, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x01);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+    }
+
+    #[test]
+    fn test_anc() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x0B, 0x80, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x80);
+        assert_eq!(cpu.regs.p, Status::CARRY | Status::NEGATIVE);
+    }
+
+    #[test]
+    fn test_arr() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x6B, 0xC0, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x60);
+        assert_eq!(cpu.regs.p, Status::CARRY);
+    }
+
+    #[test]
+    fn test_axs() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xCB, 0x01, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0x0F;
+        cpu.regs.x = 0xF3;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.x, 0x02);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+    }
+
+    #[test]
+    fn test_dcp() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xC7, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x11);
+        cpu.regs.a = 0x10;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x10);
+        assert_eq!(cpu.regs.p, Status::CARRY | Status::ZERO);
+    }
+
+    #[test]
+    fn test_isc() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xE7, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x0F);
+        cpu.regs.a = 0x30;
+        cpu.regs.p.remove(Status::all());
+        cpu.regs.p.insert(Status::CARRY);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x10);
+        assert_eq!(cpu.regs.a, 0x20);
+    }
+
+    #[test]
+    fn test_lax() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA7, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x80);
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x80);
+        assert_eq!(cpu.regs.x, 0x80);
+        assert!(cpu.regs.p.contains(Status::NEGATIVE));
+    }
+
+    #[test]
+    fn test_rla() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x27, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x81);
+        cpu.regs.a = 0xFF;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x02);
+        assert_eq!(cpu.regs.a, 0x02);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+    }
+
+    #[test]
+    fn test_rra() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x67, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x03);
+        cpu.regs.a = 0x10;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        // 0x03 >> 1 = 0x01 with carry, then 0x10 + 0x01 + carry
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x01);
+        assert_eq!(cpu.regs.a, 0x12);
+    }
+
+    #[test]
+    fn test_sax() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x87, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0x3C;
+        cpu.regs.x = 0x0F;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x0C);
+    }
+
+    #[test]
+    fn test_slo() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x07, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x81);
+        cpu.regs.a = 0x01;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x02);
+        assert_eq!(cpu.regs.a, 0x03);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+    }
+
+    #[test]
+    fn test_sre() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x47, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x03);
+        cpu.regs.a = 0x03;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0x01);
+        assert_eq!(cpu.regs.a, 0x02);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+    }
+
+    #[test]
+    fn test_unofficial_nop() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x1A, 0x80, 0xFF, 0x0C, 0xFF, 0x02, 0x00]));
+        cpu.power_on();
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8006);
+        assert_eq!(cpu.cycles(), 7 + 2 + 2 + 4);
+    }
+
+    #[test]
+    fn test_trace_line() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x4C, 0xF5, 0xC5]));
+        cpu.power_on();
+        cpu.regs.p = Status::INTERRUPT | Status::BREAK2;
+
+        assert_eq!(
+            cpu.trace_line(),
+            "8000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+        );
+    }
+
+    #[test]
+    fn test_trace_line_with_operand() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB1, 0x89, 0x04, 0xA9]));
+        cpu.power_on();
+        cpu.mem.write_word(0x0089, 0x0300);
+        cpu.mem.write_byte(0x0300, 0x89);
+        cpu.regs.p = Status::INTERRUPT | Status::BREAK2;
+        cpu.cycles = 2000;
+
+        assert_eq!(
+            cpu.trace_line(),
+            "8000  B1 89     LDA ($89),Y = 0300 @ 0300 = 89  A:00 X:00 Y:00 P:24 SP:FD PPU: 17,203 CYC:2000",
+        );
+        cpu.step().unwrap();
+        assert_eq!(&cpu.trace_line()[..32], "8002  04 A9    *NOP $A9 = 02    ");
+    }
+
+    #[test]
+    fn test_tracer() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let lines  = Rc::new(RefCell::new(Vec::new()));
+        let cloned = Rc::clone(&lines);
+        let mut cpu = Cpu::new(MyVec::new(vec![0xEA, 0xEA, 0x00]));
+        cpu.power_on();
+        cpu.set_tracer(Some(Box::new(move |line: &str| cloned.borrow_mut().push(line.to_string()))));
+        run_until_brk(&mut cpu);
+
+        let lines = lines.borrow();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("8000  EA        NOP"));
+        assert!(lines[1].starts_with("8001  EA        NOP"));
+    }
+
+    #[test]
+    fn test_absolute_x() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xBD, 0x00, 0x02, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0210, 0x42);
+        cpu.regs.x = 0x10;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+        assert_eq!(cpu.cycles(), 7 + 4);
+    }
+
+    #[test]
+    fn test_absolute_y_page_crossed() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x99, 0xFF, 0x02, 0xB9, 0xFF, 0x02, 0x00]));
+        cpu.power_on();
+        cpu.regs.a = 0x42;
+        cpu.regs.y = 0x01;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0300), 0x42);
+        assert_eq!(cpu.cycles(), 7 + 5 + (4 + 1));
+    }
+
+    #[test]
+    fn test_absolute_x_wraps_around_memory() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xBD, 0xFF, 0xFF, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0001, 0x42);
+        cpu.regs.x = 0x02;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+    }
+
+    #[test]
+    fn test_zero_page_x_wraps_around_zero_page() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB5, 0xFF, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0001, 0x42);
+        cpu.mem.write_byte(0x0101, 0x24);
+        cpu.regs.x = 0x02;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+    }
+
+    #[test]
+    fn test_zero_page_y_wraps_around_zero_page() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB6, 0x80, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x0000, 0x42);
+        cpu.mem.write_byte(0x0100, 0x24);
+        cpu.regs.y = 0x80;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.x, 0x42);
+    }
+
+    #[test]
+    fn test_indirect_x_wraps_around_zero_page() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA1, 0xFE, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x00FF, 0x34);
+        cpu.mem.write_byte(0x0000, 0x02);
+        cpu.mem.write_byte(0x0100, 0x03); // Never be touched if movement is correct
+        cpu.mem.write_byte(0x0234, 0x42);
+        cpu.regs.x = 0x01;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+    }
+
+    #[test]
+    fn test_indirect_x_pointer_wraps_around_zero_page() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xA1, 0xF0, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0x0010, 0x0234);
+        cpu.mem.write_byte(0x0234, 0x42);
+        cpu.regs.x = 0x20;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+    }
+
+    #[test]
+    fn test_indirect_y_wraps_around_zero_page() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB1, 0xFF, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_byte(0x00FF, 0x30);
+        cpu.mem.write_byte(0x0000, 0x02);
+        cpu.mem.write_byte(0x0100, 0x03); // Never be touched if movement is correct
+        cpu.mem.write_byte(0x0234, 0x42);
+        cpu.regs.y = 0x04;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+    }
+
+    #[test]
+    fn test_indirect_y_wraps_around_memory() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0xB1, 0x10, 0x00]));
+        cpu.power_on();
+        cpu.mem.write_word(0x0010, 0xFFFF);
+        cpu.mem.write_byte(0x0001, 0x42);
+        cpu.regs.y = 0x02;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+    }
+
+    #[test]
+    fn test_unofficial_nop_page_crossed() {
+        let mut cpu = Cpu::new(MyVec::new(vec![0x1C, 0xFF, 0x02, 0x00]));
+        cpu.power_on();
+        cpu.regs.x = 0x01;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.cycles(), 7 + (4 + 1));
+    }
+    #[test]
+    fn test_65c02_rejects_unofficial_opcode_as_nop() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x03, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8001);
+        assert_eq!(cpu.cycles(), 7 + 1);
+    }
+
+    #[test]
+    fn test_65c02_zero_page_indirect() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xB2, 0x10, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.mem.write_word(0x0010, 0x0300);
+        cpu.mem.write_byte(0x0300, 0x42);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x42);
+        assert_eq!(cpu.cycles(), 7 + 5);
+    }
+
+    #[test]
+    fn test_65c02_jmp_indirect_crosses_page() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x6C, 0xFF, 0x02]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.mem.write_byte(0x02FF, 0x34);
+        cpu.mem.write_byte(0x0300, 0x12);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x1234);
+        assert_eq!(cpu.cycles(), 7 + 6);
+    }
+
+    #[test]
+    fn test_65c02_jmp_absolute_indirect_x() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x7C, 0x00, 0x03]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.x = 0x02;
+        cpu.mem.write_word(0x0302, 0x1234);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x1234);
+    }
+
+    #[test]
+    fn test_65c02_inc_and_dec_accumulator() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x1A, 0x1A, 0x3A, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.a = 0xFF;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x00);
+        assert!(cpu.regs.p.contains(Status::ZERO));
+    }
+
+    #[test]
+    fn test_65c02_bit_immediate() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x89, 0xC0, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.a = 0x3F;
+        cpu.regs.p.remove(Status::all());
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.p, Status::ZERO);
+    }
+
+    #[test]
+    fn test_bra() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x80, 0x02, 0xFF, 0xFF, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.pc, 0x8004);
+        assert_eq!(cpu.cycles(), 7 + 3);
+    }
+
+    #[test]
+    fn test_phx_and_ply() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xDA, 0x7A, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.x = 0x80;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.y, 0x80);
+        assert!(cpu.regs.p.contains(Status::NEGATIVE));
+    }
+
+    #[test]
+    fn test_phy_and_plx() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x5A, 0xFA, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.y = 0x42;
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.x, 0x42);
+    }
+
+    #[test]
+    fn test_stz() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x9C, 0x00, 0x03, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.mem.write_byte(0x0300, 0xFF);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0300), 0x00);
+    }
+
+    #[test]
+    fn test_trb_and_tsb() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x14, 0x10, 0x04, 0x11, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.a = 0x0F;
+        cpu.mem.write_byte(0x0010, 0xFF);
+        cpu.mem.write_byte(0x0011, 0xF0);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0xF0);
+        assert_eq!(cpu.mem.read_byte(0x0011), 0xFF);
+        assert!(cpu.regs.p.contains(Status::ZERO));
+    }
+
+    #[test]
+    fn test_rmb_and_smb() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x07, 0x10, 0xF7, 0x11, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0xFF);
+        cpu.mem.write_byte(0x0011, 0x00);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.mem.read_byte(0x0010), 0xFE);
+        assert_eq!(cpu.mem.read_byte(0x0011), 0x80);
+    }
+
+    #[test]
+    fn test_bbr_and_bbs() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x0F, 0x10, 0x01, 0x8F, 0x10, 0x01, 0xFF, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x01);
+        run_until_brk(&mut cpu);
+
+        // Bbr0 is not taken, and Bbs0 is taken
+        assert_eq!(cpu.regs.pc, 0x8007);
+        assert_eq!(cpu.cycles(), 7 + 5 + 6);
+    }
+
+    #[test]
+    fn test_bbr_and_bbs_asm() {
+        let source = "
+                    BBR0 $10, skip1
+            skip1:  BBS0 $10, skip2
+                    BBS7 $10, skip2
+            skip2:  BRK
+        ";
+        let mut cpu = Cpu::with_variant(MyVec::from_asm(source, CpuVariant::Wdc65C02), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.mem.write_byte(0x0010, 0x01);
+        run_until_brk(&mut cpu);
+
+        // Bbr0 is not taken, and Bbs0 is taken
+        assert_eq!(cpu.regs.pc, 0x8009);
+        assert_eq!(cpu.cycles(), 7 + 5 + 6);
+    }
+
+    #[test]
+    fn test_wai() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xCB, 0xE8, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.pc, 0x8001);
+        assert_eq!(cpu.regs.x, 0x00);
+
+        // Masked irq resumes the cpu without servicing it
+        cpu.set_irq_line(true);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.regs.x, 0x01);
+    }
+
+    #[test]
+    fn test_stp() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xDB, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+
+        assert_eq!(cpu.step().unwrap_err(), CpuError::Jammed { pc: 0x8000, opcode: 0xDB });
+    }
+
+    #[test]
+    fn test_65c02_brk_clears_decimal() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.p.insert(Status::DECIMAL);
+        cpu.step().unwrap();
+
+        assert!(!cpu.regs.p.contains(Status::DECIMAL));
+    }
+
+    #[test]
+    fn test_65c02_decimal_adc() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0x69, 0x46, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.a = 0x58;
+        cpu.regs.p.insert(Status::DECIMAL | Status::CARRY);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x05);
+        assert!(cpu.regs.p.contains(Status::CARRY));
+        assert!(!cpu.regs.p.contains(Status::NEGATIVE));
+        assert_eq!(cpu.cycles(), 7 + 2 + 1);
+    }
+
+    #[test]
+    fn test_65c02_decimal_sbc() {
+        let mut cpu = Cpu::with_variant(MyVec::new(vec![0xE9, 0x01, 0x00]), CpuVariant::Wdc65C02);
+        cpu.power_on();
+        cpu.regs.a = 0x00;
+        cpu.regs.p.insert(Status::DECIMAL | Status::CARRY);
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 0x99);
+        assert!(!cpu.regs.p.contains(Status::CARRY));
+        assert!(cpu.regs.p.contains(Status::NEGATIVE));
+    }
+
+    #[test]
+    fn test_assembled_program() {
+        let source = "
+                    LDX #$05
+                    LDA #$00
+            loop:   JSR add
+                    DEX
+                    BNE loop
+                    BRK
+            add:    CLC
+                    ADC #$03
+                    RTS
+        ";
+        let mut cpu = Cpu::new(MyVec::from_asm(source, CpuVariant::default()));
+        cpu.power_on();
+        run_until_brk(&mut cpu);
+
+        assert_eq!(cpu.regs.a, 15);
+        assert_eq!(cpu.regs.x, 0);
+    }
 }
@@ -0,0 +1,562 @@
+//! A module that provide mini two-pass assembler to write programs as 6502 source
+//!
+//! A line is `label: MNEMONIC operand ; comment`, and every part can be omitted.
+//! Operands are written in standard syntax like `#$10`, `$0200,X` or `($20),Y`.
+//! Values are hex (`$FF`), binary (`%1010`), decimal, or labels, optionally added
+//! or subtracted (`table+1`). `<` and `>` take low and high byte of the value.
+//!
+//! Directives:
+//! - `.org addr`: place following bytes at `addr`
+//! - `.byte value, ...`: put 8-bit values
+//! - `.word value, ...`: put 16-bit values as little endian
+//!
+//! Zero page addressing is chosen when the value is known in the first pass and
+//! fits in a byte, so labels defined later are assembled as absolute.
+
+use std::collections::HashMap;
+use std::fmt;
+use super::memory::Memory;
+use super::opcode::*;
+use super::variant::CpuVariant;
+use crate::core::types::*;
+
+/// Bytes produced by the assembler
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assembly {
+    /// Address of the first byte
+    pub origin: Address,
+    /// Assembled bytes. Gaps made by `.org` are filled with 0.
+    pub bytes: Vec<Byte>,
+}
+
+impl Assembly {
+    /// Write the bytes to `mem` from the origin.
+    pub fn write_to(&self, mem: &mut dyn Memory) {
+        for (index, byte) in self.bytes.iter().enumerate() {
+            mem.write_byte(self.origin.wrapping_add(index as Address), *byte);
+        }
+    }
+}
+
+/// The error that represent invalid source at `line` (1-based)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assemble `source` for the default cpu variant.
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    assemble_with_variant(source, CpuVariant::default())
+}
+
+/// Assemble `source` with mnemonics and addressing modes `variant` supports.
+pub fn assemble_with_variant(source: &str, variant: CpuVariant) -> Result<Assembly, AsmError> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| parse_line(text, variant).map_err(|message| AsmError { line: index + 1, message }))
+        .collect::<Result<Vec<Line>, AsmError>>()?;
+
+    let mut assembler = Assembler { variant, labels: HashMap::new(), origin: None, bytes: Vec::new() };
+    let selected = assembler.first_pass(&lines)?;
+    assembler.second_pass(&lines, &selected)?;
+
+    Ok(Assembly { origin: assembler.origin.unwrap_or(0), bytes: assembler.bytes })
+}
+
+struct Line {
+    labels: Vec<String>,
+    statement: Option<Statement>,
+}
+
+enum Statement {
+    Org(String),
+    Byte(Vec<String>),
+    Word(Vec<String>),
+    Instruction { name: Mnemonic, operand: Operand },
+}
+
+// Operand as written. Addressing mode is decided by the assembler.
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(String),
+    Direct(String),
+    DirectX(String),
+    DirectY(String),
+    Indirect(String),
+    IndirectX(String),
+    IndirectY(String),
+    Pair(String, String),
+}
+
+struct Assembler {
+    variant: CpuVariant,
+    labels: HashMap<String, Address>,
+    origin: Option<Address>,
+    bytes: Vec<Byte>,
+}
+
+impl Assembler {
+    // Define labels and select opcode of each instruction.
+    fn first_pass(&mut self, lines: &[Line]) -> Result<Vec<Option<(Opcode, &'static OpcodeInfo)>>, AsmError> {
+        let mut selected = Vec::new();
+        let mut pc: u32  = 0;
+        for (index, line) in lines.iter().enumerate() {
+            let error = |message: String| AsmError { line: index + 1, message };
+
+            for label in &line.labels {
+                if self.labels.insert(label.clone(), pc as Address).is_some() {
+                    return Err(error(format!("Label '{}' is defined twice", label)));
+                }
+            }
+
+            let mut opcode = None;
+            match &line.statement {
+                Some(Statement::Org(expr)) => {
+                    pc = match self.eval(expr).map_err(error)? {
+                        Some(value) => to_word(value).map_err(error)? as u32,
+                        None => return Err(error(".org can't refer labels defined later".to_string())),
+                    };
+                }
+                Some(Statement::Byte(exprs)) => pc += exprs.len() as u32,
+                Some(Statement::Word(exprs)) => pc += exprs.len() as u32 * 2,
+                Some(Statement::Instruction { name, operand }) => {
+                    let (op, info) = self.select_opcode(*name, operand).map_err(error)?;
+                    pc += info.byte as u32;
+                    opcode = Some((op, info));
+                }
+                None => {}
+            }
+            if pc > 0x10000 {
+                return Err(error("Program exceeds the end of memory".to_string()));
+            }
+            selected.push(opcode);
+        }
+        Ok(selected)
+    }
+
+    // Emit bytes with all labels defined.
+    fn second_pass(&mut self, lines: &[Line], selected: &[Option<(Opcode, &'static OpcodeInfo)>]) -> Result<(), AsmError> {
+        let mut pc: Address = 0;
+        for (index, line) in lines.iter().enumerate() {
+            let error = |message: String| AsmError { line: index + 1, message };
+
+            let bytes = match &line.statement {
+                Some(Statement::Org(expr)) => {
+                    let addr = to_word(self.resolve(expr).map_err(error)?).map_err(error)?;
+                    if self.origin.is_some() && (addr as usize) < self.origin.unwrap() as usize + self.bytes.len() {
+                        return Err(error(".org can't move backward".to_string()));
+                    }
+                    pc = addr;
+                    continue;
+                }
+                Some(Statement::Byte(exprs)) => exprs
+                    .iter()
+                    .map(|expr| self.resolve(expr).and_then(to_byte))
+                    .collect::<Result<Vec<Byte>, String>>()
+                    .map_err(error)?,
+                Some(Statement::Word(exprs)) => exprs
+                    .iter()
+                    .map(|expr| self.resolve(expr).and_then(to_word))
+                    .collect::<Result<Vec<Word>, String>>()
+                    .map_err(error)?
+                    .into_iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .collect(),
+                Some(Statement::Instruction { operand, .. }) => {
+                    let (opcode, info) = selected[index].unwrap();
+                    self.encode(opcode, info, operand, pc).map_err(error)?
+                }
+                None => continue,
+            };
+
+            let origin = *self.origin.get_or_insert(pc);
+            self.bytes.resize(pc as usize - origin as usize, 0);
+            self.bytes.extend_from_slice(&bytes);
+            pc = pc.wrapping_add(bytes.len() as Address);
+        }
+        Ok(())
+    }
+
+    // Choose addressing mode from how the operand is written.
+    // Zero page is preferred if the value is already known and fits in a byte.
+    fn select_opcode(&self, name: Mnemonic, operand: &Operand) -> Result<(Opcode, &'static OpcodeInfo), String> {
+        let fits_zero_page = |expr: &String| matches!(self.eval(expr), Ok(Some(value)) if (0..0x100).contains(&value));
+        let has            = |mode: AddressingMode| find_opcode(self.variant, name, mode).is_some();
+        let zero_page_or   = |expr: &String, zero_page: AddressingMode, absolute: AddressingMode| {
+            if (fits_zero_page(expr) || !has(absolute)) && has(zero_page) { zero_page } else { absolute }
+        };
+
+        let mode = match operand {
+            Operand::None if has(AddressingMode::Implied) => AddressingMode::Implied,
+            Operand::None | Operand::Accumulator => AddressingMode::Accumulator,
+            Operand::Immediate(_) => AddressingMode::Immediate,
+            Operand::Direct(_) if has(AddressingMode::Relative) => AddressingMode::Relative,
+            Operand::Direct(expr)  => zero_page_or(expr, AddressingMode::ZeroPage, AddressingMode::Absolute),
+            Operand::DirectX(expr) => zero_page_or(expr, AddressingMode::ZeroPageX, AddressingMode::AbsoluteX),
+            Operand::DirectY(expr) => zero_page_or(expr, AddressingMode::ZeroPageY, AddressingMode::AbsoluteY),
+            Operand::Indirect(_) if has(AddressingMode::Indirect) => AddressingMode::Indirect,
+            Operand::Indirect(_)   => AddressingMode::ZeroPageIndirect,
+            Operand::IndirectX(_) if has(AddressingMode::IndirectX) => AddressingMode::IndirectX,
+            Operand::IndirectX(_)  => AddressingMode::AbsoluteIndirectX,
+            Operand::IndirectY(_)  => AddressingMode::IndirectY,
+            Operand::Pair(_, _)    => AddressingMode::ZeroPageRelative,
+        };
+
+        find_opcode(self.variant, name, mode)
+            .ok_or_else(|| format!("{} doesn't take {:?} addressing mode", format_name(name), mode))
+    }
+
+    fn encode(&self, opcode: Opcode, info: &OpcodeInfo, operand: &Operand, pc: Address) -> Result<Vec<Byte>, String> {
+        // Offset is relative to the next instruction
+        let offset = |expr: &String| -> Result<Byte, String> {
+            let next   = pc as i64 + info.byte as i64;
+            let offset = self.resolve(expr)? - next;
+            if (-128..=127).contains(&offset) {
+                Ok(offset as i8 as Byte)
+            } else {
+                Err(format!("Branch target is too far ({} bytes)", offset))
+            }
+        };
+
+        let mut bytes = vec![opcode];
+        match (operand, info.mode) {
+            (Operand::None, _) | (Operand::Accumulator, _) => {}
+            (Operand::Direct(expr), AddressingMode::Relative) => bytes.push(offset(expr)?),
+            (Operand::Pair(zero_page, target), _) => {
+                bytes.push(to_byte(self.resolve(zero_page)?)?);
+                bytes.push(offset(target)?);
+            }
+            (Operand::Immediate(expr), _)   | (Operand::Direct(expr), _)    |
+            (Operand::DirectX(expr), _)     | (Operand::DirectY(expr), _)   |
+            (Operand::Indirect(expr), _)    | (Operand::IndirectX(expr), _) |
+            (Operand::IndirectY(expr), _) => {
+                let value = self.resolve(expr)?;
+                if info.byte == 2 {
+                    bytes.push(to_byte(value)?);
+                } else {
+                    bytes.extend_from_slice(&to_word(value)?.to_le_bytes());
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    // Return None if the expression refers an undefined label.
+    fn eval(&self, expr: &str) -> Result<Option<i64>, String> {
+        let (expr, shift) = match expr.strip_prefix('<') {
+            Some(rest) => (rest, Some(0)),
+            None => match expr.strip_prefix('>') {
+                Some(rest) => (rest, Some(8)),
+                None       => (expr, None),
+            },
+        };
+
+        let mut sum = 0;
+        for (sign, term) in split_terms(expr)? {
+            let value = match parse_number(term)? {
+                Some(value) => value,
+                None if is_identifier(term) => match self.labels.get(term) {
+                    Some(addr) => *addr as i64,
+                    None       => return Ok(None),
+                },
+                None => return Err(format!("Invalid value '{}'", term)),
+            };
+            sum += sign * value;
+        }
+
+        Ok(Some(match shift {
+            Some(shift) => (sum >> shift) & 0xFF,
+            None        => sum,
+        }))
+    }
+
+    fn resolve(&self, expr: &str) -> Result<i64, String> {
+        self.eval(expr)?.ok_or_else(|| format!("Undefined label in '{}'", expr))
+    }
+}
+
+// Official opcode is preferred, as unofficial tables have same instructions.
+fn find_opcode(variant: CpuVariant, name: Mnemonic, mode: AddressingMode) -> Option<(Opcode, &'static OpcodeInfo)> {
+    let lookup = |opcode: Opcode| {
+        variant.lookup_opcode(opcode).filter(|info| info.name == name && info.mode == mode).map(|info| (opcode, info))
+    };
+
+    (0..=0xFF)
+        .filter(|opcode| OPCODE_TABLE[*opcode as usize].is_some())
+        .find_map(lookup)
+        .or_else(|| (0..=0xFF).find_map(lookup))
+}
+
+fn find_mnemonic(variant: CpuVariant, text: &str) -> Option<Mnemonic> {
+    (0..=0xFF)
+        .filter_map(|opcode| variant.lookup_opcode(opcode))
+        .map(|info| info.name)
+        .find(|name| format_name(*name).eq_ignore_ascii_case(text))
+}
+
+fn format_name(name: Mnemonic) -> String {
+    format!("{:?}", name).to_uppercase()
+}
+
+fn parse_line(text: &str, variant: CpuVariant) -> Result<Line, String> {
+    let mut rest   = text.split(';').next().unwrap_or("").trim();
+    let mut labels = Vec::new();
+    while let Some((label, after)) = rest.split_once(':') {
+        if !is_identifier(label.trim()) {
+            break;
+        }
+        labels.push(label.trim().to_string());
+        rest = after.trim();
+    }
+
+    if rest.is_empty() {
+        return Ok(Line { labels, statement: None });
+    }
+
+    let (word, operand) = match rest.split_once(char::is_whitespace) {
+        Some((word, operand)) => (word, operand.split_whitespace().collect::<String>()),
+        None => (rest, String::new()),
+    };
+    let list = || operand.split(',').map(|expr| expr.to_string()).collect::<Vec<String>>();
+
+    let statement = match word.to_lowercase().as_str() {
+        ".org"  => Statement::Org(operand.clone()),
+        ".byte" => Statement::Byte(list()),
+        ".word" => Statement::Word(list()),
+        _ if word.starts_with('.') => return Err(format!("Unknown directive '{}'", word)),
+        _ => match find_mnemonic(variant, word) {
+            Some(name) => Statement::Instruction { name, operand: parse_operand(&operand)? },
+            None => return Err(format!("Unknown mnemonic '{}'", word)),
+        },
+    };
+    Ok(Line { labels, statement: Some(statement) })
+}
+
+// `text` must not contain whitespace.
+fn parse_operand(text: &str) -> Result<Operand, String> {
+    let upper = text.to_uppercase();
+    let inner = |prefix: usize, suffix: usize| text[prefix..text.len() - suffix].to_string();
+
+    let operand = if text.is_empty() {
+        Operand::None
+    } else if upper == "A" {
+        Operand::Accumulator
+    } else if let Some(expr) = text.strip_prefix('#') {
+        Operand::Immediate(expr.to_string())
+    } else if upper.starts_with('(') && upper.ends_with("),Y") {
+        Operand::IndirectY(inner(1, 3))
+    } else if upper.starts_with('(') && upper.ends_with(",X)") {
+        Operand::IndirectX(inner(1, 3))
+    } else if upper.starts_with('(') && upper.ends_with(')') {
+        Operand::Indirect(inner(1, 1))
+    } else if upper.ends_with(",X") {
+        Operand::DirectX(inner(0, 2))
+    } else if upper.ends_with(",Y") {
+        Operand::DirectY(inner(0, 2))
+    } else if let Some((first, second)) = text.split_once(',') {
+        Operand::Pair(first.to_string(), second.to_string())
+    } else {
+        Operand::Direct(text.to_string())
+    };
+
+    match &operand {
+        Operand::Immediate(expr) | Operand::Direct(expr) | Operand::DirectX(expr) | Operand::DirectY(expr) |
+        Operand::Indirect(expr)  | Operand::IndirectX(expr) | Operand::IndirectY(expr) if expr.is_empty() || expr.contains(['(', ')', ',']) => {
+            Err(format!("Invalid operand '{}'", text))
+        }
+        _ => Ok(operand),
+    }
+}
+
+// Split `a+b-c` to [(1, a), (1, b), (-1, c)].
+fn split_terms(expr: &str) -> Result<Vec<(i64, &str)>, String> {
+    let mut terms = Vec::new();
+    let mut sign  = 1;
+    let mut start = 0;
+    for (index, c) in expr.char_indices() {
+        if (c == '+' || c == '-') && index > start {
+            terms.push((sign, &expr[start..index]));
+            sign  = if c == '+' { 1 } else { -1 };
+            start = index + 1;
+        } else if c == '-' && index == start {
+            sign  = -sign;
+            start = index + 1;
+        }
+    }
+    if start >= expr.len() {
+        return Err(format!("Invalid value '{}'", expr));
+    }
+    terms.push((sign, &expr[start..]));
+    Ok(terms)
+}
+
+// Return None if `text` is not a number.
+fn parse_number(text: &str) -> Result<Option<i64>, String> {
+    let (digits, radix) = match text.chars().next() {
+        Some('$') => (&text[1..], 16),
+        Some('%') => (&text[1..], 2),
+        Some(c) if c.is_ascii_digit() => (text, 10),
+        _ => return Ok(None),
+    };
+    i64::from_str_radix(digits, radix).map(Some).map_err(|_| format!("Invalid number '{}'", text))
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn to_byte(value: i64) -> Result<Byte, String> {
+    if (-0x80..=0xFF).contains(&value) {
+        Ok(value as Byte)
+    } else {
+        Err(format!("Value {} doesn't fit in a byte", value))
+    }
+}
+
+fn to_word(value: i64) -> Result<Word, String> {
+    if (-0x8000..=0xFFFF).contains(&value) {
+        Ok(value as Word)
+    } else {
+        Err(format!("Value {} doesn't fit in a word", value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::cpu::disasm;
+
+    fn bytes(source: &str) -> Vec<Byte> {
+        assemble(source).unwrap().bytes
+    }
+
+    #[test]
+    fn test_assemble_addressing_modes() {
+        let source = "
+            LDA #$01
+            LDA $02
+            LDA $03,X
+            LDX $04,Y
+            LDA $0200
+            LDA $0300,x
+            LDA $0400,Y
+            JMP ($FFFC)
+            LDA ($05,X)
+            LDA ($06), Y
+            ASL A
+            ASL
+            NOP
+        ";
+
+        assert_eq!(bytes(source), vec![
+            0xA9, 0x01, 0xA5, 0x02, 0xB5, 0x03, 0xB6, 0x04, 0xAD, 0x00, 0x02, 0xBD, 0x00, 0x03,
+            0xB9, 0x00, 0x04, 0x6C, 0xFC, 0xFF, 0xA1, 0x05, 0xB1, 0x06, 0x0A, 0x0A, 0xEA,
+        ]);
+    }
+
+    #[test]
+    fn test_assemble_labels() {
+        let source = "
+                    .org $8000
+            start:  LDX #<data      ; comment
+            loop:   DEX
+                    BNE loop
+                    JMP end
+            data:   .byte $01, 2, %11, >data
+                    .word start, data+1
+            end:    BRK
+        ";
+        let assembly = assemble(source).unwrap();
+
+        assert_eq!(assembly.origin, 0x8000);
+        assert_eq!(assembly.bytes, vec![
+            0xA2, 0x08, 0xCA, 0xD0, 0xFD, 0x4C, 0x10, 0x80,
+            0x01, 0x02, 0x03, 0x80, 0x00, 0x80, 0x09, 0x80,
+            0x00,
+        ]);
+    }
+
+    #[test]
+    fn test_assemble_forward_zero_page_label_as_absolute() {
+        let source = "
+            earlier: NOP
+                     LDA later
+                     LDA earlier
+            later:   BRK
+        ";
+
+        assert_eq!(bytes(source), vec![0xEA, 0xAD, 0x06, 0x00, 0xA5, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_assemble_org_fills_gap() {
+        let assembly = assemble(".org $0200\n.byte 1\n.org $0203\n.byte 2").unwrap();
+
+        assert_eq!(assembly.origin, 0x0200);
+        assert_eq!(assembly.bytes, vec![0x01, 0x00, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn test_assemble_65c02() {
+        let source = "
+            .org $8000
+            LDA ($10)
+            JMP ($1234,X)
+            BBR0 $10, target
+            STZ $20
+            target: BRA target
+        ";
+        let assembly = assemble_with_variant(source, CpuVariant::Wdc65C02).unwrap();
+
+        assert_eq!(assembly.bytes, vec![0xB2, 0x10, 0x7C, 0x34, 0x12, 0x0F, 0x10, 0x02, 0x64, 0x20, 0x80, 0xFE]);
+        assert!(assemble("STZ $20").is_err());
+    }
+
+    #[test]
+    fn test_assemble_unofficial() {
+        assert_eq!(bytes("LAX $10\nNOP $20\nSBC #$01"), vec![0xA7, 0x10, 0x04, 0x20, 0xE9, 0x01]);
+        assert!(assemble_with_variant("LAX $10", CpuVariant::OfficialOnly).is_err());
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let error = |source: &str| assemble(source).unwrap_err();
+
+        assert_eq!(error("NOP\nFOO #1"), AsmError { line: 2, message: "Unknown mnemonic 'FOO'".to_string() });
+        assert_eq!(error("JMP nowhere").line, 1);
+        assert_eq!(error("LDA #$100").message, "Value 256 doesn't fit in a byte");
+        assert_eq!(error("STA #$10").message, "STA doesn't take Immediate addressing mode");
+        assert_eq!(error("a: NOP\na: NOP").line, 2);
+        assert!(error(".org $10\n.byte 1\n.org $00").message.contains("backward"));
+        assert!(error("loop: BNE far\n.org $0100\nfar: NOP").message.contains("too far"));
+    }
+
+    #[test]
+    fn test_assemble_matches_disassembler() {
+        let source = "
+            .org $C000
+            LDA ($20),Y
+            BNE $C012
+            STA $0200,X
+            JMP ($FFFC)
+        ";
+        let assembly = assemble(source).unwrap();
+        let lines    = disasm::disassemble(&assembly.bytes, assembly.origin, CpuVariant::default());
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+
+        assert_eq!(texts, vec!["LDA ($20),Y", "BNE $C012", "STA $0200,X", "JMP ($FFFC)"]);
+    }
+}
@@ -0,0 +1,796 @@
+//! A module that provide cycle-accurate cpu core which advance one cycle per tick
+//!
+//! `Cpu` executes a whole instruction at once, so the bus is accessed only for
+//! the data the instruction needs. `CycleCpu` accesses the bus exactly once per
+//! cycle as NMOS 6502 does, including dummy reads of indexed addressing and
+//! double writes of read-modify-write instructions. Devices connected to the bus
+//! see every access at the cycle it happens.
+//!
+//! Operations of instructions are shared with `Cpu`. This core only decides
+//! which address is accessed at each cycle.
+
+use super::*;
+
+// Memory that hold a byte read from the bus, and record a write to the bus.
+// Instructions of `Cpu` are executed against this, and this core puts the
+// recorded write on the bus at the right cycle.
+#[derive(Default)]
+struct Latch {
+    value: Byte,
+    write: Option<(Address, Byte)>,
+}
+
+impl Memory for Latch {
+    fn read_byte(&self, _addr: Address) -> Byte {
+        match self.write {
+            Some((_, value)) => value,
+            None             => self.value,
+        }
+    }
+
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        self.write = Some((addr, value));
+    }
+}
+
+// Sequence of bus accesses an instruction take
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Interrupt,
+    Brk,
+    Jsr,
+    Rts,
+    Rti,
+    Push,
+    Pull,
+    JmpAbsolute,
+    JmpIndirect,
+    Branch,
+    Implied,
+    Read,
+    Write,
+    Modify,
+}
+
+impl Kind {
+    fn of(info: &OpcodeInfo) -> Self {
+        match (info.name, info.mode) {
+            (Mnemonic::Brk, _) => Kind::Brk,
+            (Mnemonic::Jsr, _) => Kind::Jsr,
+            (Mnemonic::Rts, _) => Kind::Rts,
+            (Mnemonic::Rti, _) => Kind::Rti,
+            (Mnemonic::Pha, _) | (Mnemonic::Php, _) => Kind::Push,
+            (Mnemonic::Pla, _) | (Mnemonic::Plp, _) => Kind::Pull,
+            (Mnemonic::Jmp, AddressingMode::Absolute) => Kind::JmpAbsolute,
+            (Mnemonic::Jmp, _) => Kind::JmpIndirect,
+            (_, AddressingMode::Relative) => Kind::Branch,
+            (_, AddressingMode::Implied) | (_, AddressingMode::Accumulator) => Kind::Implied,
+            (Mnemonic::Sta, _) | (Mnemonic::Stx, _) | (Mnemonic::Sty, _) | (Mnemonic::Sax, _) |
+            (Mnemonic::Ahx, _) | (Mnemonic::Shx, _) | (Mnemonic::Shy, _) | (Mnemonic::Tas, _) => Kind::Write,
+            (Mnemonic::Asl, _) | (Mnemonic::Lsr, _) | (Mnemonic::Rol, _) | (Mnemonic::Ror, _) |
+            (Mnemonic::Inc, _) | (Mnemonic::Dec, _) | (Mnemonic::Slo, _) | (Mnemonic::Sre, _) |
+            (Mnemonic::Rla, _) | (Mnemonic::Rra, _) | (Mnemonic::Dcp, _) | (Mnemonic::Isc, _) => Kind::Modify,
+            _ => Kind::Read,
+        }
+    }
+}
+
+/// The cpu that advance one cycle per `tick`, accessing memory `M` once per cycle.
+///
+/// Only NMOS variants are supported, as 65C02 accesses the bus differently.
+pub struct CycleCpu<M: Memory> {
+    // Registers, interrupt lines and instructions
+    core: Cpu<Latch>,
+    mem:  M,
+    // Cycle in the current instruction. 0 means the next tick fetch an opcode.
+    step: u8,
+    kind: Kind,
+    opcode: Opcode,
+    info: &'static OpcodeInfo,
+    // Address of the opcode
+    pc: Address,
+    // Temporaries of addressing
+    addr:    Address,
+    pointer: Byte,
+    value:   Byte,
+    crossed: bool,
+    // The cycle when the address is ready and the data is accessed
+    access: Option<u8>,
+    write:  Option<(Address, Byte)>,
+    // Interrupts polled at the end of the last instruction
+    pending:   bool,
+    requested: bool,
+    serviced:  Option<Interrupt>,
+}
+
+impl<M: Memory> CycleCpu<M> {
+    pub fn new(mem: M) -> Self {
+        Self::with_variant(mem, CpuVariant::default())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `variant` is 65C02.
+    pub fn with_variant(mem: M, variant: CpuVariant) -> Self {
+        assert!(!variant.is_cmos(), "CycleCpu doesn't support {:?}", variant);
+
+        Self {
+            core: Cpu::with_variant(Latch::default(), variant),
+            mem,
+            step: 0,
+            kind: Kind::Implied,
+            opcode: 0xEA,
+            info: OPCODE_TABLE[0xEA].as_ref().unwrap(),
+            pc: 0,
+            addr: 0,
+            pointer: 0,
+            value: 0,
+            crossed: false,
+            access: None,
+            write: None,
+            pending: false,
+            requested: false,
+            serviced: None,
+        }
+    }
+
+    /// Return the memory connected to the cpu.
+    pub fn mem(&self) -> &M {
+        &self.mem
+    }
+
+    /// Return the memory connected to the cpu as mutable.
+    pub fn mem_mut(&mut self) -> &mut M {
+        &mut self.mem
+    }
+
+    /// Return the program counter.
+    pub fn pc(&self) -> Address {
+        self.core.regs.pc
+    }
+
+    /// Return the number of cycles the cpu has consumed since power on.
+    pub fn cycles(&self) -> u64 {
+        self.core.cycles
+    }
+
+    /// Return true if no instruction is in progress.
+    pub fn is_at_boundary(&self) -> bool {
+        self.step == 0
+    }
+
+    /// Return a snapshot of the cpu state. This should be called at instruction boundary.
+    pub fn state(&self) -> CpuState {
+        self.core.state()
+    }
+
+    /// Restore the cpu state from a snapshot, and abort the instruction in progress.
+    pub fn set_state(&mut self, state: &CpuState) {
+        self.core.set_state(state);
+        self.abort();
+    }
+
+    /// Set whether nmi line is asserted or not. See `Cpu::set_nmi_line`.
+    pub fn set_nmi_line(&mut self, asserted: bool) {
+        self.core.set_nmi_line(asserted);
+    }
+
+    /// Set whether irq line is asserted or not. See `Cpu::set_irq_line`.
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.core.set_irq_line(asserted);
+    }
+
+    /// Initialize registers as the cpu is powered on, and jump to reset vector.
+    pub fn power_on(&mut self) {
+        self.core.power_on();
+        self.core.regs.pc = self.mem.read_word(0xFFFC);
+        self.abort();
+    }
+
+    /// Reset the cpu, and jump to reset vector.
+    pub fn reset(&mut self) {
+        self.core.reset();
+        self.core.regs.pc = self.mem.read_word(0xFFFC);
+        self.abort();
+    }
+
+    /// Advance one cycle, which access the bus once.
+    ///
+    /// If the fetched opcode can't be executed, the program counter is left at the
+    /// opcode and an error is returned without consuming the cycle.
+    pub fn tick(&mut self) -> Result<(), CpuError> {
+        // Interrupts are polled at the end of the second to last cycle.
+        let requested = self.is_interrupt_requested();
+
+        let finished = if self.step == 0 { self.decode()? } else { self.tick_instruction()? };
+        self.core.cycles += 1;
+        self.mem.tick(1);
+
+        if finished {
+            // Taken branch which doesn't cross page doesn't poll in its last cycle.
+            self.pending = if self.kind == Kind::Branch && self.step == 2 { self.requested } else { requested };
+            self.step    = 0;
+        } else {
+            self.step += 1;
+        }
+        self.requested = requested;
+        Ok(())
+    }
+
+    /// Tick until an instruction is finished. See `Cpu::step`.
+    pub fn step(&mut self) -> Result<StepInfo, CpuError> {
+        let start         = self.core.cycles;
+        let mut interrupt = None;
+        loop {
+            self.tick()?;
+            if self.step != 0 {
+                continue;
+            }
+            match self.serviced.take() {
+                Some(serviced) => interrupt = Some(serviced),
+                None => {
+                    return Ok(StepInfo {
+                        opcode: self.opcode,
+                        name: self.info.name,
+                        mode: self.info.mode,
+                        cycles: self.core.cycles - start,
+                        interrupt,
+                    })
+                }
+            }
+        }
+    }
+
+    fn abort(&mut self) {
+        self.step      = 0;
+        self.access    = None;
+        self.pending   = false;
+        self.requested = false;
+        self.serviced  = None;
+    }
+
+    fn is_interrupt_requested(&self) -> bool {
+        self.core.nmi_pending || (self.core.irq_line && !self.core.regs.p.contains(Status::INTERRUPT))
+    }
+
+    // The first cycle fetch an opcode, or start interrupt sequence.
+    fn decode(&mut self) -> Result<bool, CpuError> {
+        self.access = None;
+        if self.pending {
+            self.pending = false;
+            self.kind    = Kind::Interrupt;
+            self.read(self.core.regs.pc);
+            return Ok(false);
+        }
+
+        self.pc    = self.core.regs.pc;
+        let opcode = self.fetch();
+        let info   = match self.core.variant.lookup_opcode(opcode) {
+            Some(info) if info.name != Mnemonic::Kil => info,
+            info => {
+                self.core.regs.pc = self.pc;
+                return Err(match info {
+                    Some(_) => CpuError::Jammed { pc: self.pc, opcode },
+                    None    => CpuError::IllegalOpcode { pc: self.pc, opcode },
+                });
+            }
+        };
+        self.opcode = opcode;
+        self.info   = info;
+        self.kind   = Kind::of(info);
+        Ok(false)
+    }
+
+    // Return true if the instruction is finished at this cycle.
+    fn tick_instruction(&mut self) -> Result<bool, CpuError> {
+        let pc = self.core.regs.pc;
+        match (self.kind, self.step) {
+            (Kind::Interrupt, 1) => { self.read(pc); }
+            (Kind::Brk, 1)       => { self.fetch(); }
+            (Kind::Interrupt, 2) | (Kind::Brk, 2) => self.push(pc.to_le_bytes()[1]),
+            (Kind::Interrupt, 3) | (Kind::Brk, 3) => self.push(pc.to_le_bytes()[0]),
+            (Kind::Interrupt, 4) | (Kind::Brk, 4) => {
+                // Nmi requested until here hijacks irq and brk.
+                let interrupt = if self.core.nmi_pending { Interrupt::Nmi } else { Interrupt::Irq };
+                self.core.nmi_pending &= interrupt != Interrupt::Nmi;
+                self.serviced = Some(interrupt);
+
+                let p = self.core.regs.p | Status::BREAK2;
+                self.push(if self.kind == Kind::Brk { p | Status::BREAK1 } else { p - Status::BREAK1 }.bits());
+            }
+            (Kind::Interrupt, 5) | (Kind::Brk, 5) => {
+                self.value = self.read(self.serviced.unwrap().vector());
+                self.core.regs.p.insert(Status::INTERRUPT);
+            }
+            (Kind::Interrupt, _) | (Kind::Brk, _) => {
+                let msb = self.read(self.serviced.unwrap().vector().wrapping_add(1));
+                self.core.regs.pc = Word::from_le_bytes([self.value, msb]);
+                if self.kind == Kind::Brk {
+                    self.serviced = None;
+                }
+                return Ok(true);
+            }
+
+            (Kind::Jsr, 1) => self.value = self.fetch(),
+            (Kind::Jsr, 2) => { self.read(self.stack()); }
+            (Kind::Jsr, 3) => self.push(pc.to_le_bytes()[1]),
+            (Kind::Jsr, 4) => self.push(pc.to_le_bytes()[0]),
+            (Kind::Jsr, _) => {
+                let msb = self.read(pc);
+                self.core.regs.pc = Word::from_le_bytes([self.value, msb]);
+                return Ok(true);
+            }
+
+            (Kind::Rts, 1) | (Kind::Rti, 1) => { self.read(pc); }
+            (Kind::Rts, 2) | (Kind::Rti, 2) => { self.read(self.stack()); }
+            (Kind::Rts, 3) => self.value = self.pull(),
+            (Kind::Rts, 4) => {
+                let msb = self.pull();
+                self.core.regs.pc = Word::from_le_bytes([self.value, msb]);
+            }
+            (Kind::Rts, _) => {
+                self.fetch();
+                return Ok(true);
+            }
+            (Kind::Rti, 3) => {
+                let p = self.pull();
+                self.core.regs.p = (Status::from_bits_truncate(p) - Status::BREAK1) | Status::BREAK2;
+            }
+            (Kind::Rti, 4) => self.value = self.pull(),
+            (Kind::Rti, _) => {
+                let msb = self.pull();
+                self.core.regs.pc = Word::from_le_bytes([self.value, msb]);
+                return Ok(true);
+            }
+
+            (Kind::Push, 1) | (Kind::Pull, 1) | (Kind::Implied, 1) => {
+                self.read(pc);
+                if self.kind == Kind::Implied {
+                    self.execute(0)?;
+                    return Ok(true);
+                }
+            }
+            (Kind::Push, _) => {
+                if let Some((addr, value)) = self.execute(0)? {
+                    self.write(addr, value);
+                }
+                return Ok(true);
+            }
+            (Kind::Pull, 2) => { self.read(self.stack()); }
+            (Kind::Pull, _) => {
+                let value = self.read(0x0100 | self.core.regs.s.wrapping_add(1) as Address);
+                self.execute(value)?;
+                return Ok(true);
+            }
+
+            (Kind::JmpAbsolute, 1) | (Kind::JmpIndirect, 1) => self.value = self.fetch(),
+            (Kind::JmpAbsolute, _) => {
+                let msb = self.read(pc);
+                self.core.regs.pc = Word::from_le_bytes([self.value, msb]);
+                return Ok(true);
+            }
+            (Kind::JmpIndirect, 2) => {
+                let msb   = self.fetch();
+                self.addr = Word::from_le_bytes([self.value, msb]);
+            }
+            (Kind::JmpIndirect, 3) => self.value = self.read(self.addr),
+            (Kind::JmpIndirect, _) => {
+                // The pointer doesn't carry to the high byte.
+                let msb = self.read((self.addr & 0xFF00) | (self.addr.wrapping_add(1) & 0x00FF));
+                self.core.regs.pc = Word::from_le_bytes([self.value, msb]);
+                return Ok(true);
+            }
+
+            (Kind::Branch, 1) => {
+                let offset = self.fetch();
+                self.addr  = self.core.regs.pc.wrapping_add(offset as i8 as Address);
+                return Ok(!self.is_branch_taken());
+            }
+            (Kind::Branch, 2) => {
+                self.read(pc);
+                if pc & 0xFF00 == self.addr & 0xFF00 {
+                    self.core.regs.pc = self.addr;
+                    return Ok(true);
+                }
+                // Low byte is added first, and high byte is fixed in the next cycle.
+                self.core.regs.pc = (pc & 0xFF00) | (self.addr & 0x00FF);
+            }
+            (Kind::Branch, _) => {
+                self.read(pc);
+                self.core.regs.pc = self.addr;
+                return Ok(true);
+            }
+
+            _ => return self.tick_operand(),
+        }
+        Ok(false)
+    }
+
+    // Addressing cycles of instructions which access memory.
+    fn tick_operand(&mut self) -> Result<bool, CpuError> {
+        if let Some(start) = self.access {
+            return self.tick_access(self.step - start);
+        }
+
+        let index = match self.info.mode {
+            AddressingMode::AbsoluteY | AddressingMode::ZeroPageY | AddressingMode::IndirectY => self.core.regs.y,
+            _ => self.core.regs.x,
+        };
+        match (self.info.mode, self.step) {
+            (AddressingMode::Immediate, _) => {
+                self.addr = self.core.regs.pc;
+                self.core.regs.pc = self.core.regs.pc.wrapping_add(1);
+                return self.start_access();
+            }
+            (AddressingMode::ZeroPage, _) => {
+                self.addr = self.fetch() as Address;
+                self.address_ready();
+            }
+            (AddressingMode::ZeroPageX, 1) | (AddressingMode::ZeroPageY, 1) |
+            (AddressingMode::IndirectX, 1) | (AddressingMode::IndirectY, 1) => self.pointer = self.fetch(),
+            (AddressingMode::ZeroPageX, _) | (AddressingMode::ZeroPageY, _) => {
+                self.read(self.pointer as Address);
+                self.addr = self.pointer.wrapping_add(index) as Address;
+                self.address_ready();
+            }
+            (AddressingMode::Absolute, 1) | (AddressingMode::AbsoluteX, 1) | (AddressingMode::AbsoluteY, 1) => {
+                self.value = self.fetch();
+            }
+            (AddressingMode::Absolute, _) => {
+                let msb   = self.fetch();
+                self.addr = Word::from_le_bytes([self.value, msb]);
+                self.address_ready();
+            }
+            (AddressingMode::AbsoluteX, 2) | (AddressingMode::AbsoluteY, 2) => {
+                let msb = self.fetch();
+                self.add_index(msb, index);
+            }
+            (AddressingMode::IndirectX, 2) => {
+                self.read(self.pointer as Address);
+                self.pointer = self.pointer.wrapping_add(index);
+            }
+            (AddressingMode::IndirectX, 3) | (AddressingMode::IndirectY, 2) => {
+                self.value = self.read(self.pointer as Address);
+            }
+            (AddressingMode::IndirectX, _) => {
+                let msb   = self.read(self.pointer.wrapping_add(1) as Address);
+                self.addr = Word::from_le_bytes([self.value, msb]);
+                self.address_ready();
+            }
+            (AddressingMode::IndirectY, 3) => {
+                let msb = self.read(self.pointer.wrapping_add(1) as Address);
+                self.add_index(msb, index);
+            }
+            // Address without carry is read first. Read instructions use it if
+            // page isn't crossed, otherwise it is a dummy read.
+            (AddressingMode::AbsoluteX, _) | (AddressingMode::AbsoluteY, _) | (AddressingMode::IndirectY, _) => {
+                if self.kind == Kind::Read && !self.crossed {
+                    return self.start_access();
+                }
+                self.read(self.addr);
+                if self.crossed {
+                    self.addr = self.addr.wrapping_add(0x0100);
+                }
+                self.address_ready();
+            }
+            (mode, _) => {
+                self.core.regs.pc = self.pc;
+                return Err(CpuError::InvalidAddressingMode { pc: self.pc, name: self.info.name, mode });
+            }
+        }
+        Ok(false)
+    }
+
+    // Data cycles after the address is ready.
+    fn tick_access(&mut self, cycle: u8) -> Result<bool, CpuError> {
+        match (self.kind, cycle) {
+            (Kind::Write, _) => {
+                if let Some((addr, value)) = self.execute(0)? {
+                    self.write(addr, value);
+                }
+            }
+            (Kind::Modify, 0) => {
+                self.value = self.read(self.addr);
+                return Ok(false);
+            }
+            // Read-modify-write instructions write the original value while modifying it.
+            (Kind::Modify, 1) => {
+                self.write(self.addr, self.value);
+                self.write = self.execute(self.value)?;
+                return Ok(false);
+            }
+            (Kind::Modify, _) => {
+                if let Some((addr, value)) = self.write.take() {
+                    self.write(addr, value);
+                }
+            }
+            _ => {
+                let value = self.read(self.addr);
+                self.execute(value)?;
+            }
+        }
+        Ok(true)
+    }
+
+    // Data is accessed in this cycle.
+    fn start_access(&mut self) -> Result<bool, CpuError> {
+        self.access = Some(self.step);
+        self.tick_access(0)
+    }
+
+    // Data is accessed from the next cycle.
+    fn address_ready(&mut self) {
+        self.access = Some(self.step + 1);
+    }
+
+    // Index is added to the low byte in this cycle, and carry is added to `msb` later.
+    fn add_index(&mut self, msb: Byte, index: Byte) {
+        let (lsb, crossed) = self.value.overflowing_add(index);
+        self.addr    = Word::from_le_bytes([lsb, msb]);
+        self.crossed = crossed;
+    }
+
+    // Execute the operation of the instruction with `value` as data read from the bus.
+    // Return the data the instruction write.
+    fn execute(&mut self, value: Byte) -> Result<Option<(Address, Byte)>, CpuError> {
+        self.core.mem = Latch { value, write: None };
+        let cycles    = self.core.cycles;
+        let result    = self.core.execute(self.pc, self.opcode, self.info, self.addr);
+        self.core.cycles = cycles;
+
+        result.map(|_| self.core.mem.write.take())
+    }
+
+    fn is_branch_taken(&self) -> bool {
+        let p = self.core.regs.p;
+        match self.info.name {
+            Mnemonic::Bcc => !p.contains(Status::CARRY),
+            Mnemonic::Bcs =>  p.contains(Status::CARRY),
+            Mnemonic::Beq =>  p.contains(Status::ZERO),
+            Mnemonic::Bmi =>  p.contains(Status::NEGATIVE),
+            Mnemonic::Bne => !p.contains(Status::ZERO),
+            Mnemonic::Bpl => !p.contains(Status::NEGATIVE),
+            Mnemonic::Bvc => !p.contains(Status::OVERFLOW),
+            Mnemonic::Bvs =>  p.contains(Status::OVERFLOW),
+            _ => false,
+        }
+    }
+
+    fn read(&mut self, addr: Address) -> Byte {
+        self.mem.read_byte(addr)
+    }
+
+    fn write(&mut self, addr: Address, value: Byte) {
+        self.mem.write_byte(addr, value);
+    }
+
+    fn fetch(&mut self) -> Byte {
+        let value = self.read(self.core.regs.pc);
+        self.core.regs.pc = self.core.regs.pc.wrapping_add(1);
+        value
+    }
+
+    fn stack(&self) -> Address {
+        0x0100 | self.core.regs.s as Address
+    }
+
+    fn push(&mut self, value: Byte) {
+        self.write(self.stack(), value);
+        self.core.regs.s = self.core.regs.s.wrapping_sub(1);
+    }
+
+    fn pull(&mut self) -> Byte {
+        self.core.regs.s = self.core.regs.s.wrapping_add(1);
+        self.read(self.stack())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    // Flat memory which record every access as (address, value, is_write).
+    struct Bus {
+        ram: Vec<Byte>,
+        log: RefCell<Vec<(Address, Byte, bool)>>,
+    }
+
+    impl Bus {
+        fn new(source: &str) -> Self {
+            let mut bus = Self { ram: vec![0; 0x10000], log: RefCell::new(Vec::new()) };
+            asm::assemble(&format!(".org $8000\n{}", source)).unwrap().write_to(&mut bus);
+            bus.ram[0xFFFC] = 0x00;
+            bus.ram[0xFFFD] = 0x80;
+            bus.log.borrow_mut().clear();
+            bus
+        }
+    }
+
+    impl Memory for Bus {
+        fn read_byte(&self, addr: Address) -> Byte {
+            let value = self.ram[addr as usize];
+            self.log.borrow_mut().push((addr, value, false));
+            value
+        }
+
+        fn write_byte(&mut self, addr: Address, value: Byte) {
+            self.ram[addr as usize] = value;
+            self.log.borrow_mut().push((addr, value, true));
+        }
+    }
+
+    fn new_cpu(source: &str) -> CycleCpu<Bus> {
+        let mut cpu = CycleCpu::new(Bus::new(source));
+        cpu.power_on();
+        cpu.mem().log.borrow_mut().clear();
+        cpu
+    }
+
+    fn step_and_take_log(cpu: &mut CycleCpu<Bus>) -> Vec<(Address, Byte, bool)> {
+        cpu.step().unwrap();
+        cpu.mem().log.take()
+    }
+
+    #[test]
+    fn test_tick_one_cycle() {
+        let mut cpu = new_cpu("LDA $0200");
+        cpu.tick().unwrap();
+
+        assert!(!cpu.is_at_boundary());
+        assert_eq!(cpu.mem().log.borrow().len(), 1);
+
+        cpu.tick().unwrap();
+        cpu.tick().unwrap();
+        cpu.tick().unwrap();
+
+        assert!(cpu.is_at_boundary());
+        assert_eq!(cpu.cycles(), 7 + 4);
+    }
+
+    #[test]
+    fn test_dummy_read_on_page_cross() {
+        let mut cpu = new_cpu("LDX #$01\nLDA $02FF,X\nLDA $0200,X");
+        cpu.step().unwrap();
+        cpu.mem().log.take();
+
+        assert_eq!(step_and_take_log(&mut cpu), vec![
+            (0x8002, 0xBD, false), (0x8003, 0xFF, false), (0x8004, 0x02, false),
+            (0x0200, 0x00, false), (0x0300, 0x00, false),
+        ]);
+        assert_eq!(step_and_take_log(&mut cpu).len(), 4);
+    }
+
+    #[test]
+    fn test_store_always_read_before_write() {
+        let mut cpu = new_cpu("STA $0200,X");
+
+        assert_eq!(step_and_take_log(&mut cpu), vec![
+            (0x8000, 0x9D, false), (0x8001, 0x00, false), (0x8002, 0x02, false),
+            (0x0200, 0x00, false), (0x0200, 0x00, true),
+        ]);
+    }
+
+    #[test]
+    fn test_read_modify_write_double_write() {
+        let mut cpu = new_cpu("INC $10");
+        cpu.mem_mut().ram[0x10] = 0x41;
+
+        assert_eq!(step_and_take_log(&mut cpu), vec![
+            (0x8000, 0xE6, false), (0x8001, 0x10, false),
+            (0x0010, 0x41, false), (0x0010, 0x41, true), (0x0010, 0x42, true),
+        ]);
+    }
+
+    #[test]
+    fn test_jsr_and_rts() {
+        let mut cpu = new_cpu("JSR sub\nBRK\nsub: RTS");
+
+        assert_eq!(step_and_take_log(&mut cpu), vec![
+            (0x8000, 0x20, false), (0x8001, 0x04, false), (0x01FD, 0x00, false),
+            (0x01FD, 0x80, true), (0x01FC, 0x02, true), (0x8002, 0x80, false),
+        ]);
+        assert_eq!(step_and_take_log(&mut cpu), vec![
+            (0x8004, 0x60, false), (0x8005, 0x00, false), (0x01FB, 0x00, false),
+            (0x01FC, 0x02, false), (0x01FD, 0x80, false), (0x8002, 0x80, false),
+        ]);
+        assert_eq!(cpu.pc(), 0x8003);
+    }
+
+    #[test]
+    fn test_taken_branch_across_page() {
+        let mut cpu = new_cpu(".org $80FD\nBNE $8110");
+        cpu.set_state(&CpuState { pc: 0x80FD, ..cpu.state() });
+
+        assert_eq!(step_and_take_log(&mut cpu), vec![
+            (0x80FD, 0xD0, false), (0x80FE, 0x11, false), (0x80FF, 0x00, false), (0x8010, 0x00, false),
+        ]);
+        assert_eq!(cpu.pc(), 0x8110);
+    }
+
+    #[test]
+    fn test_irq_sequence() {
+        let mut cpu = new_cpu("CLI\nNOP\nNOP");
+        cpu.mem_mut().ram[0xFFFE] = 0x00;
+        cpu.mem_mut().ram[0xFFFF] = 0x90;
+        cpu.mem_mut().ram[0x9000] = 0xEA;
+        cpu.set_irq_line(true);
+
+        // Cli changes interrupt flag in its last cycle, so irq is taken after next instruction.
+        cpu.step().unwrap();
+        let info = cpu.step().unwrap();
+
+        assert_eq!(info.interrupt, None);
+        assert_eq!(cpu.pc(), 0x8002);
+
+        cpu.mem().log.take();
+        let info = cpu.step().unwrap();
+        let log  = cpu.mem().log.take();
+
+        assert_eq!(info.interrupt, Some(Interrupt::Irq));
+        assert_eq!(info.cycles, 7 + 2);
+        assert_eq!(&log[..7], &[
+            (0x8002, 0xEA, false), (0x8002, 0xEA, false), (0x01FD, 0x80, true),
+            (0x01FC, 0x02, true), (0x01FB, 0x20, true), (0xFFFE, 0x00, false), (0xFFFF, 0x90, false),
+        ]);
+    }
+
+    #[test]
+    fn test_nmi_hijacks_brk() {
+        let mut cpu = new_cpu("BRK");
+        cpu.mem_mut().ram[0xFFFA] = 0x00;
+        cpu.mem_mut().ram[0xFFFB] = 0x90;
+        for _ in 0..3 {
+            cpu.tick().unwrap();
+        }
+        cpu.set_nmi_line(true);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.pc(), 0x9000);
+        assert_eq!(cpu.mem().ram[0x01FB], 0x34);
+    }
+
+    #[test]
+    fn test_illegal_opcode() {
+        let mut cpu = CycleCpu::with_variant(Bus::new(".byte $02"), CpuVariant::OfficialOnly);
+        cpu.power_on();
+
+        assert_eq!(cpu.tick(), Err(CpuError::IllegalOpcode { pc: 0x8000, opcode: 0x02 }));
+        assert_eq!(cpu.pc(), 0x8000);
+        assert!(cpu.is_at_boundary());
+    }
+
+    // Every opcode must have same result and cycles as `Cpu`.
+    #[test]
+    fn test_same_as_cpu() {
+        for opcode in 0..=0xFF {
+            if NMOS_DECODE_TABLE[opcode as usize].unwrap().name == Mnemonic::Kil {
+                continue;
+            }
+
+            let mut bus = Bus::new("");
+            for (index, byte) in bus.ram.iter_mut().enumerate() {
+                *byte = (index as u32).wrapping_mul(0x9E37_79B9).to_le_bytes()[3];
+            }
+            bus.ram[0x8000] = opcode;
+            bus.ram[0xFFFC] = 0x00;
+            bus.ram[0xFFFD] = 0x80;
+            let ram = bus.ram.clone();
+
+            let mut expected = Cpu::with_variant(Bus { ram, log: RefCell::new(Vec::new()) }, CpuVariant::Nmos6502);
+            let mut actual   = CycleCpu::with_variant(bus, CpuVariant::Nmos6502);
+            for cpu_state in [0x00, 0xA5, 0xFF] {
+                let state = CpuState { a: cpu_state, x: cpu_state ^ 0x3C, y: cpu_state.wrapping_add(0x81), pc: 0x8000, s: 0xFD, p: 0x24 | (cpu_state & 0xC3), ..CpuState::default() };
+                expected.set_state(&state);
+                actual.set_state(&state);
+
+                let expected_cycles = expected.step().unwrap().cycles;
+                let actual_cycles   = actual.step().unwrap().cycles;
+
+                assert_eq!(actual.state(), expected.state(), "0x{:02X}", opcode);
+                assert_eq!(actual_cycles, expected_cycles, "0x{:02X}", opcode);
+                assert!(actual.mem().ram == expected.mem.ram, "0x{:02X}", opcode);
+            }
+        }
+    }
+}
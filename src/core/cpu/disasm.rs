@@ -0,0 +1,210 @@
+//! A module that provide disassembler to list instructions in assembler syntax
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use super::memory::Memory;
+use super::opcode::*;
+use super::variant::CpuVariant;
+use crate::core::types::*;
+
+/// A disassembled instruction.
+///
+/// Bytes that are not an instruction are listed as `.byte` without mnemonic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// Address of the first byte
+    pub addr: Address,
+    /// Raw bytes of the instruction
+    pub bytes: Vec<Byte>,
+    /// Mnemonic of the instruction, or None for `.byte`
+    pub name: Option<Mnemonic>,
+    /// Instruction in assembler syntax like `LDA ($20),Y`
+    pub text: String,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        write!(f, "{:04X}  {:<8}  {}", self.addr, bytes.join(" "), self.text)
+    }
+}
+
+/// Disassemble `bytes` as if it is placed at `origin`.
+///
+/// An instruction cut off at the end of `bytes` is listed as `.byte`.
+pub fn disassemble(bytes: &[Byte], origin: Address, variant: CpuVariant) -> Vec<Line> {
+    let mut lines  = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let addr = origin.wrapping_add(offset as Address);
+        let line = decode(addr, variant, |i| bytes.get(offset + i as usize).copied());
+        offset += line.bytes.len();
+        lines.push(line);
+    }
+    lines
+}
+
+/// Disassemble instructions which start in `range` of `mem`.
+///
+/// The last instruction may read bytes beyond the end of `range`.
+pub fn disassemble_memory(mem: &dyn Memory, range: RangeInclusive<Address>, variant: CpuVariant) -> Vec<Line> {
+    let mut lines = Vec::new();
+    // Use u32 not to wrap around at the end of memory
+    let mut addr  = *range.start() as u32;
+    while addr <= *range.end() as u32 {
+        let line = disassemble_one(mem, addr as Address, variant);
+        addr += line.bytes.len() as u32;
+        lines.push(line);
+    }
+    lines
+}
+
+/// Disassemble one instruction at `addr` of `mem`.
+///
+/// Memory is read by `peek_byte`, so this has no side effects on devices.
+pub fn disassemble_one(mem: &dyn Memory, addr: Address, variant: CpuVariant) -> Line {
+    decode(addr, variant, |i| Some(mem.peek_byte(addr.wrapping_add(i))))
+}
+
+// `fetch` returns the byte `i` bytes after `addr`, or None if it doesn't exist.
+// The first byte must exist.
+fn decode(addr: Address, variant: CpuVariant, fetch: impl Fn(Address) -> Option<Byte>) -> Line {
+    let opcode = fetch(0).unwrap_or_default();
+    let info   = variant.lookup_opcode(opcode);
+    let bytes  = info.and_then(|info| (0..info.byte as Address).map(&fetch).collect::<Option<Vec<Byte>>>());
+
+    match (info, bytes) {
+        (Some(info), Some(bytes)) => Line {
+            addr,
+            text: format_instruction(info, addr, &bytes),
+            name: Some(info.name),
+            bytes,
+        },
+        _ => Line {
+            addr,
+            bytes: vec![opcode],
+            name: None,
+            text: format!(".byte ${:02X}", opcode),
+        },
+    }
+}
+
+fn format_instruction(info: &OpcodeInfo, addr: Address, bytes: &[Byte]) -> String {
+    let name = format!("{:?}", info.name).to_uppercase();
+    let lsb  = bytes.get(1).copied().unwrap_or(0);
+    let msb  = bytes.get(2).copied().unwrap_or(0);
+    let word = Word::from_le_bytes([lsb, msb]);
+
+    // Offset is signed and relative to the next instruction
+    let target = |offset: Byte| addr.wrapping_add(info.byte as Address).wrapping_add(offset as i8 as Address);
+
+    let operand = match info.mode {
+        AddressingMode::Implied     => return name,
+        AddressingMode::Accumulator => "A".to_string(),
+        AddressingMode::Immediate   => format!("#${:02X}", lsb),
+        AddressingMode::ZeroPage    => format!("${:02X}", lsb),
+        AddressingMode::ZeroPageX   => format!("${:02X},X", lsb),
+        AddressingMode::ZeroPageY   => format!("${:02X},Y", lsb),
+        AddressingMode::Absolute    => format!("${:04X}", word),
+        AddressingMode::AbsoluteX   => format!("${:04X},X", word),
+        AddressingMode::AbsoluteY   => format!("${:04X},Y", word),
+        AddressingMode::Indirect    => format!("(${:04X})", word),
+        AddressingMode::IndirectX   => format!("(${:02X},X)", lsb),
+        AddressingMode::IndirectY   => format!("(${:02X}),Y", lsb),
+        AddressingMode::Relative    => format!("${:04X}", target(lsb)),
+        AddressingMode::ZeroPageIndirect  => format!("(${:02X})", lsb),
+        AddressingMode::AbsoluteIndirectX => format!("(${:04X},X)", word),
+        AddressingMode::ZeroPageRelative  => format!("${:02X},${:04X}", lsb, target(msb)),
+    };
+    format!("{} {}", name, operand)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct MyVec {
+        vec: Vec<u8>,
+    }
+
+    impl Memory for MyVec {
+        fn read_byte(&self, addr: Address) -> Byte {
+            self.vec.get(addr as usize).copied().unwrap_or(0)
+        }
+
+        fn write_byte(&mut self, addr: Address, value: Byte) {
+            self.vec[addr as usize] = value;
+        }
+    }
+
+    fn texts(lines: &[Line]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let bytes = [0xB1, 0x20, 0xD0, 0x0E, 0x4C, 0xF5, 0xC5, 0x0A, 0xEA];
+        let lines = disassemble(&bytes, 0xC000, CpuVariant::default());
+
+        assert_eq!(texts(&lines), vec!["LDA ($20),Y", "BNE $C012", "JMP $C5F5", "ASL A", "NOP"]);
+        assert_eq!(lines[2].addr, 0xC004);
+        assert_eq!(lines[2].bytes, vec![0x4C, 0xF5, 0xC5]);
+        assert_eq!(lines[2].name, Some(Mnemonic::Jmp));
+    }
+
+    #[test]
+    fn test_disassemble_addressing_modes() {
+        let bytes = [
+            0xA9, 0x01, 0xA5, 0x02, 0xB5, 0x03, 0xB6, 0x04, 0xAD, 0x00, 0x02, 0xBD, 0x00, 0x03,
+            0xB9, 0x00, 0x04, 0x6C, 0xFC, 0xFF, 0xA1, 0x05, 0xF0, 0xFE,
+        ];
+        let lines = disassemble(&bytes, 0x8000, CpuVariant::default());
+
+        assert_eq!(texts(&lines), vec![
+            "LDA #$01", "LDA $02", "LDA $03,X", "LDX $04,Y", "LDA $0200", "LDA $0300,X",
+            "LDA $0400,Y", "JMP ($FFFC)", "LDA ($05,X)", "BEQ $8016",
+        ]);
+    }
+
+    #[test]
+    fn test_disassemble_illegal_and_truncated() {
+        let bytes = [0x02, 0xAD, 0x00];
+        let lines = disassemble(&bytes, 0x8000, CpuVariant::OfficialOnly);
+
+        assert_eq!(texts(&lines), vec![".byte $02", ".byte $AD", "BRK"]);
+        assert_eq!(lines[0].name, None);
+    }
+
+    #[test]
+    fn test_disassemble_variant() {
+        let bytes = [0xA7, 0x10, 0x0F, 0x10, 0xFD];
+
+        assert_eq!(texts(&disassemble(&bytes, 0x8000, CpuVariant::Ricoh2A03)), vec!["LAX $10", "SLO $FD10"]);
+        assert_eq!(texts(&disassemble(&bytes, 0x8000, CpuVariant::Wdc65C02)), vec!["SMB2 $10", "BBR0 $10,$8002"]);
+    }
+
+    #[test]
+    fn test_disassemble_memory() {
+        let mem   = MyVec { vec: vec![0xEA, 0x8D, 0x00, 0x02, 0x60] };
+        let lines = disassemble_memory(&mem, 0x0001..=0x0004, CpuVariant::default());
+
+        assert_eq!(texts(&lines), vec!["STA $0200", "RTS"]);
+        assert_eq!(lines[1].addr, 0x0004);
+    }
+
+    #[test]
+    fn test_disassemble_memory_at_end_of_memory() {
+        let mem   = MyVec { vec: vec![0x20, 0x00] };
+        let lines = disassemble_memory(&mem, 0xFFFE..=0xFFFF, CpuVariant::default());
+
+        assert_eq!(texts(&lines), vec!["BRK", "BRK"]);
+        assert_eq!(disassemble_one(&mem, 0xFFFF, CpuVariant::default()).text, "BRK");
+    }
+
+    #[test]
+    fn test_line_display() {
+        let lines = disassemble(&[0x4C, 0xF5, 0xC5], 0xC000, CpuVariant::default());
+
+        assert_eq!(lines[0].to_string(), "C000  4C F5 C5  JMP $C5F5");
+    }
+}
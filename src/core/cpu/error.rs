@@ -0,0 +1,34 @@
+//! A module that provide errors cpu can cause while executing instructions
+
+use std::fmt;
+use super::opcode::*;
+use crate::core::types::*;
+
+/// The enum that represent error while the cpu is executing an instruction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CpuError {
+    /// The opcode at `pc` is not in OPCODE_TABLE.
+    IllegalOpcode { pc: Address, opcode: Opcode },
+    /// The opcode at `pc` has addressing mode the mnemonic can't take.
+    InvalidAddressingMode { pc: Address, name: Mnemonic, mode: AddressingMode },
+    /// The cpu is halted by a Kil or Stp instruction at `pc`. Only reset can recover it.
+    Jammed { pc: Address, opcode: Opcode },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::IllegalOpcode { pc, opcode } => {
+                write!(f, "Illegal opcode 0x{:02X} at 0x{:04X}", opcode, pc)
+            }
+            CpuError::InvalidAddressingMode { pc, name, mode } => {
+                write!(f, "{:?} is not exist on {:?} at 0x{:04X}", mode, name, pc)
+            }
+            CpuError::Jammed { pc, opcode } => {
+                write!(f, "Cpu is jammed by opcode 0x{:02X} at 0x{:04X}", opcode, pc)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
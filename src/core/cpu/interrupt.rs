@@ -0,0 +1,22 @@
+//! A module that provide hardware interrupts of cpu
+
+use crate::core::types::*;
+
+/// The enum that represent hardware interrupts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interrupt {
+    /// Non-maskable interrupt. This is edge-triggered and can't be masked.
+    Nmi,
+    /// Interrupt request. This is level-triggered and masked by interrupt flag.
+    Irq,
+}
+
+impl Interrupt {
+    /// Return address where the address of interrupt handler is stored
+    pub fn vector(&self) -> Address {
+        match self {
+            Interrupt::Nmi => 0xFFFA,
+            Interrupt::Irq => 0xFFFE,
+        }
+    }
+}
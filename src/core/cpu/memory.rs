@@ -14,6 +14,25 @@ pub trait Memory {
     /// Get 16-bit address and write supplied 8-bit value to the address
     fn write_byte(&mut self, addr: Address, value: Byte);
 
+    /// Get 16-bit address and return 8-bit value from the address without side effects
+    ///
+    /// Debuggers and tracers use this not to clear flags or shift registers of devices.
+    /// Memory whose read has side effects should override this.
+    fn peek_byte(&self, addr: Address) -> Byte {
+        self.read_byte(addr)
+    }
+
+    /// Get 16-bit address and return 16-bit value from the address without side effects
+    fn peek_word(&self, addr: Address) -> Word {
+        let bytes = [ self.peek_byte(addr), self.peek_byte(addr.wrapping_add(1)) ];
+        u16::from_le_bytes(bytes)
+    }
+
+    /// Called after cpu consumed `cycles` cycles, so devices can advance in step with cpu
+    ///
+    /// `Cpu` calls this once per instruction, and `CycleCpu` calls this once per cycle.
+    fn tick(&mut self, _cycles: u64) {}
+
     /// Get 16-bit address and return 16-bit value from the address
     ///
     /// The return value is encoded as native endian
@@ -32,9 +51,40 @@ pub trait Memory {
     }
 }
 
+impl<M: Memory + ?Sized> Memory for Box<M> {
+    fn read_byte(&self, addr: Address) -> Byte {
+        (**self).read_byte(addr)
+    }
+
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        (**self).write_byte(addr, value)
+    }
+
+    fn read_word(&self, addr: Address) -> Word {
+        (**self).read_word(addr)
+    }
+
+    fn write_word(&mut self, addr: Address, value: Word) {
+        (**self).write_word(addr, value)
+    }
+
+    fn peek_byte(&self, addr: Address) -> Byte {
+        (**self).peek_byte(addr)
+    }
+
+    fn peek_word(&self, addr: Address) -> Word {
+        (**self).peek_word(addr)
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        (**self).tick(cycles)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Memory;
+    use std::cell::Cell;
 
     struct MyVec {
         vec: Vec<u8>,
@@ -68,4 +118,39 @@ mod test {
         mem.write_word(0, 0x0100);
         assert_eq!(mem.vec, vec![0x00, 0x01]);
     }
+
+    #[test]
+    fn test_peek_delegates_to_read() {
+        let mem = MyVec{ vec: vec![0x00, 0x01] };
+        assert_eq!(mem.peek_byte(1), 0x01);
+        assert_eq!(mem.peek_word(0), 0x0100);
+    }
+
+    // Memory whose read clears the value like a status register
+    struct ClearOnRead {
+        value: Cell<u8>,
+    }
+
+    impl Memory for ClearOnRead {
+        fn read_byte(&self, _addr: u16) -> u8 {
+            self.value.replace(0)
+        }
+
+        fn write_byte(&mut self, _addr: u16, value: u8) {
+            self.value.set(value);
+        }
+
+        fn peek_byte(&self, _addr: u16) -> u8 {
+            self.value.get()
+        }
+    }
+
+    #[test]
+    fn test_peek_has_no_side_effect() {
+        let mem: Box<dyn Memory> = Box::new(ClearOnRead{ value: Cell::new(0x80) });
+        assert_eq!(mem.peek_byte(0), 0x80);
+        assert_eq!(mem.peek_word(0), 0x8080);
+        assert_eq!(mem.read_byte(0), 0x80);
+        assert_eq!(mem.peek_byte(0), 0x00);
+    }
 }
@@ -3,25 +3,36 @@
 //! TODO:
 //! - [ ] Clean OPCODE_TABLE with function: put together same code.
 
-use once_cell::sync::Lazy;
-use std::collections::HashMap;
-
 /// The enum that represent mnemonics 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Mnemonic {
     Adc, And, Asl, Bcc, Bcs, Beq, Bit, Bmi, Bne, Bpl, Brk, Bvc, Bvs, Clc,
     Cld, Cli, Clv, Cmp, Cpx, Cpy, Dec, Dex, Dey, Eor, Inc, Inx, Iny, Jmp,
     Jsr, Lda, Ldx, Ldy, Lsr, Nop, Ora, Pha, Php, Pla, Plp, Rol, Ror, Rti,
     Rts, Sbc, Sec, Sed, Sei, Sta, Stx, Sty, Tax, Tay, Tsx, Txa, Txs, Tya,
+
+    // Unofficial mnemonics
+    Ahx, Alr, Anc, Arr, Axs, Dcp, Isc, Kil, Las, Lax, Lxa, Rla, Rra, Sax,
+    Shx, Shy, Slo, Sre, Tas, Xaa,
+
+    // 65C02 mnemonics
+    Bra, Phx, Phy, Plx, Ply, Stp, Stz, Trb, Tsb, Wai,
+    Rmb0, Rmb1, Rmb2, Rmb3, Rmb4, Rmb5, Rmb6, Rmb7,
+    Smb0, Smb1, Smb2, Smb3, Smb4, Smb5, Smb6, Smb7,
+    Bbr0, Bbr1, Bbr2, Bbr3, Bbr4, Bbr5, Bbr6, Bbr7,
+    Bbs0, Bbs1, Bbs2, Bbs3, Bbs4, Bbs5, Bbs6, Bbs7,
 }
 
 /// The enum that represent addressing mode
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AddressingMode {
     Accumulator, Absolute, AbsoluteX, AbsoluteY,
     Immediate,   Implied,  Indirect,  IndirectX,
     IndirectY,   Relative, ZeroPage,  ZeroPageX,
     ZeroPageY,
+
+    // 65C02 addressing modes
+    ZeroPageIndirect, AbsoluteIndirectX, ZeroPageRelative,
 }
 
 /// An type that is specialized u8 type to represent opcode.
@@ -31,7 +42,7 @@ pub enum AddressingMode {
 pub type Opcode = u8;
 
 // This is used in OPCODE_TABLE to associate some infomation with opcode.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct OpcodeInfo {
     pub byte: u8,
     pub cycle: u8,
@@ -39,224 +50,534 @@ pub struct OpcodeInfo {
     pub mode: AddressingMode,
 }
 
+/// Table indexed by opcode
+pub type OpcodeTable = [Option<OpcodeInfo>; 256];
+
 impl OpcodeInfo {
-    fn new(byte: u8, cycle: u8, name: Mnemonic, mode: AddressingMode) -> Self {
+    const fn new(byte: u8, cycle: u8, name: Mnemonic, mode: AddressingMode) -> Self {
         Self { byte, cycle, name, mode }
     }
 }
 
-// Due to struct can't contain static variable, this static variable is
-// declared as global and private.
-pub static OPCODE_TABLE: Lazy<HashMap<Opcode, OpcodeInfo>> = Lazy::new(|| {
-    HashMap::from([
-        (0x69, OpcodeInfo::new(2, 2, Mnemonic::Adc, AddressingMode::Immediate)),
-        (0x65, OpcodeInfo::new(2, 3, Mnemonic::Adc, AddressingMode::ZeroPage )),
-        (0x75, OpcodeInfo::new(2, 4, Mnemonic::Adc, AddressingMode::ZeroPageX)),
-        (0x6D, OpcodeInfo::new(3, 4, Mnemonic::Adc, AddressingMode::Absolute )),
-        (0x7D, OpcodeInfo::new(3, 4, Mnemonic::Adc, AddressingMode::AbsoluteX)),
-        (0x79, OpcodeInfo::new(3, 4, Mnemonic::Adc, AddressingMode::AbsoluteY)),
-        (0x61, OpcodeInfo::new(2, 6, Mnemonic::Adc, AddressingMode::IndirectX)),
-        (0x71, OpcodeInfo::new(2, 5, Mnemonic::Adc, AddressingMode::IndirectY)),
+// Put entries to the table indexed by opcode at compile time.
+// Duplicated opcode is a bug in the table, so it fails to compile.
+const fn build_table(entries: &[(Opcode, OpcodeInfo)]) -> OpcodeTable {
+    let mut table = [None; 256];
+    let mut index = 0;
+    while index < entries.len() {
+        let (opcode, info) = entries[index];
+        assert!(table[opcode as usize].is_none(), "opcode is duplicated");
+        table[opcode as usize] = Some(info);
+        index += 1;
+    }
+    table
+}
+
+// Put entries of `overlay` over `base`.
+const fn merge_tables(base: &OpcodeTable, overlay: &OpcodeTable) -> OpcodeTable {
+    let mut table = *base;
+    let mut index = 0;
+    while index < 256 {
+        if overlay[index].is_some() {
+            table[index] = overlay[index];
+        }
+        index += 1;
+    }
+    table
+}
+
+/// Official opcodes of 6502.
+pub static OPCODE_TABLE: OpcodeTable = build_table(&[
+    (0x69, OpcodeInfo::new(2, 2, Mnemonic::Adc, AddressingMode::Immediate)),
+    (0x65, OpcodeInfo::new(2, 3, Mnemonic::Adc, AddressingMode::ZeroPage )),
+    (0x75, OpcodeInfo::new(2, 4, Mnemonic::Adc, AddressingMode::ZeroPageX)),
+    (0x6D, OpcodeInfo::new(3, 4, Mnemonic::Adc, AddressingMode::Absolute )),
+    (0x7D, OpcodeInfo::new(3, 4, Mnemonic::Adc, AddressingMode::AbsoluteX)),
+    (0x79, OpcodeInfo::new(3, 4, Mnemonic::Adc, AddressingMode::AbsoluteY)),
+    (0x61, OpcodeInfo::new(2, 6, Mnemonic::Adc, AddressingMode::IndirectX)),
+    (0x71, OpcodeInfo::new(2, 5, Mnemonic::Adc, AddressingMode::IndirectY)),
 
-        (0x29, OpcodeInfo::new(2, 2, Mnemonic::And, AddressingMode::Immediate)),
-        (0x25, OpcodeInfo::new(2, 3, Mnemonic::And, AddressingMode::ZeroPage )),
-        (0x35, OpcodeInfo::new(2, 4, Mnemonic::And, AddressingMode::ZeroPageX)),
-        (0x2D, OpcodeInfo::new(3, 4, Mnemonic::And, AddressingMode::Absolute )),
-        (0x3D, OpcodeInfo::new(3, 4, Mnemonic::And, AddressingMode::AbsoluteX)),
-        (0x39, OpcodeInfo::new(3, 4, Mnemonic::And, AddressingMode::AbsoluteY)),
-        (0x21, OpcodeInfo::new(2, 6, Mnemonic::And, AddressingMode::IndirectX)),
-        (0x31, OpcodeInfo::new(2, 5, Mnemonic::And, AddressingMode::IndirectY)),
+    (0x29, OpcodeInfo::new(2, 2, Mnemonic::And, AddressingMode::Immediate)),
+    (0x25, OpcodeInfo::new(2, 3, Mnemonic::And, AddressingMode::ZeroPage )),
+    (0x35, OpcodeInfo::new(2, 4, Mnemonic::And, AddressingMode::ZeroPageX)),
+    (0x2D, OpcodeInfo::new(3, 4, Mnemonic::And, AddressingMode::Absolute )),
+    (0x3D, OpcodeInfo::new(3, 4, Mnemonic::And, AddressingMode::AbsoluteX)),
+    (0x39, OpcodeInfo::new(3, 4, Mnemonic::And, AddressingMode::AbsoluteY)),
+    (0x21, OpcodeInfo::new(2, 6, Mnemonic::And, AddressingMode::IndirectX)),
+    (0x31, OpcodeInfo::new(2, 5, Mnemonic::And, AddressingMode::IndirectY)),
 
-        (0x0A, OpcodeInfo::new(1, 2, Mnemonic::Asl, AddressingMode::Accumulator)),
-        (0x06, OpcodeInfo::new(2, 5, Mnemonic::Asl, AddressingMode::ZeroPage   )),
-        (0x16, OpcodeInfo::new(2, 6, Mnemonic::Asl, AddressingMode::ZeroPageX  )),
-        (0x0E, OpcodeInfo::new(3, 6, Mnemonic::Asl, AddressingMode::Absolute   )),
-        (0x1E, OpcodeInfo::new(3, 7, Mnemonic::Asl, AddressingMode::AbsoluteX  )),
+    (0x0A, OpcodeInfo::new(1, 2, Mnemonic::Asl, AddressingMode::Accumulator)),
+    (0x06, OpcodeInfo::new(2, 5, Mnemonic::Asl, AddressingMode::ZeroPage   )),
+    (0x16, OpcodeInfo::new(2, 6, Mnemonic::Asl, AddressingMode::ZeroPageX  )),
+    (0x0E, OpcodeInfo::new(3, 6, Mnemonic::Asl, AddressingMode::Absolute   )),
+    (0x1E, OpcodeInfo::new(3, 7, Mnemonic::Asl, AddressingMode::AbsoluteX  )),
 
-        (0x90, OpcodeInfo::new(2, 2, Mnemonic::Bcc, AddressingMode::Relative)),
+    (0x90, OpcodeInfo::new(2, 2, Mnemonic::Bcc, AddressingMode::Relative)),
 
-        (0xB0, OpcodeInfo::new(2, 2, Mnemonic::Bcs, AddressingMode::Relative)),
+    (0xB0, OpcodeInfo::new(2, 2, Mnemonic::Bcs, AddressingMode::Relative)),
 
-        (0xF0, OpcodeInfo::new(2, 2, Mnemonic::Beq, AddressingMode::Relative)),
+    (0xF0, OpcodeInfo::new(2, 2, Mnemonic::Beq, AddressingMode::Relative)),
 
-        (0x24, OpcodeInfo::new(2, 3, Mnemonic::Bit, AddressingMode::ZeroPage)),
-        (0x2C, OpcodeInfo::new(3, 4, Mnemonic::Bit, AddressingMode::Absolute)),
+    (0x24, OpcodeInfo::new(2, 3, Mnemonic::Bit, AddressingMode::ZeroPage)),
+    (0x2C, OpcodeInfo::new(3, 4, Mnemonic::Bit, AddressingMode::Absolute)),
 
-        (0x30, OpcodeInfo::new(2, 2, Mnemonic::Bmi, AddressingMode::Relative)),
+    (0x30, OpcodeInfo::new(2, 2, Mnemonic::Bmi, AddressingMode::Relative)),
 
-        (0xD0, OpcodeInfo::new(2, 2, Mnemonic::Bne, AddressingMode::Relative)),
+    (0xD0, OpcodeInfo::new(2, 2, Mnemonic::Bne, AddressingMode::Relative)),
 
-        (0x10, OpcodeInfo::new(2, 2, Mnemonic::Bpl, AddressingMode::Relative)),
-
-        (0x00, OpcodeInfo::new(1, 7, Mnemonic::Brk, AddressingMode::Implied)),
+    (0x10, OpcodeInfo::new(2, 2, Mnemonic::Bpl, AddressingMode::Relative)),
 
-        (0x50, OpcodeInfo::new(2, 2, Mnemonic::Bvc, AddressingMode::Relative)),
+    (0x00, OpcodeInfo::new(1, 7, Mnemonic::Brk, AddressingMode::Implied)),
 
-        (0x70, OpcodeInfo::new(2, 2, Mnemonic::Bvs, AddressingMode::Relative)),
+    (0x50, OpcodeInfo::new(2, 2, Mnemonic::Bvc, AddressingMode::Relative)),
 
-        (0x18, OpcodeInfo::new(1, 2, Mnemonic::Clc, AddressingMode::Implied)),
+    (0x70, OpcodeInfo::new(2, 2, Mnemonic::Bvs, AddressingMode::Relative)),
 
-        (0xD8, OpcodeInfo::new(1, 2, Mnemonic::Cld, AddressingMode::Implied)),
+    (0x18, OpcodeInfo::new(1, 2, Mnemonic::Clc, AddressingMode::Implied)),
 
-        (0x58, OpcodeInfo::new(1, 2, Mnemonic::Cli, AddressingMode::Implied)),
+    (0xD8, OpcodeInfo::new(1, 2, Mnemonic::Cld, AddressingMode::Implied)),
 
-        (0xB8, OpcodeInfo::new(1, 2, Mnemonic::Clv, AddressingMode::Implied)),
+    (0x58, OpcodeInfo::new(1, 2, Mnemonic::Cli, AddressingMode::Implied)),
 
-        (0xC9, OpcodeInfo::new(2, 2, Mnemonic::Cmp, AddressingMode::Immediate)),
-        (0xC5, OpcodeInfo::new(2, 3, Mnemonic::Cmp, AddressingMode::ZeroPage )),
-        (0xD5, OpcodeInfo::new(2, 4, Mnemonic::Cmp, AddressingMode::ZeroPageX)),
-        (0xCD, OpcodeInfo::new(3, 4, Mnemonic::Cmp, AddressingMode::Absolute )),
-        (0xDD, OpcodeInfo::new(3, 4, Mnemonic::Cmp, AddressingMode::AbsoluteX)),
-        (0xD9, OpcodeInfo::new(3, 4, Mnemonic::Cmp, AddressingMode::AbsoluteY)),
-        (0xC1, OpcodeInfo::new(2, 6, Mnemonic::Cmp, AddressingMode::IndirectX)),
-        (0xD1, OpcodeInfo::new(2, 5, Mnemonic::Cmp, AddressingMode::IndirectY)),
+    (0xB8, OpcodeInfo::new(1, 2, Mnemonic::Clv, AddressingMode::Implied)),
 
-        (0xE0, OpcodeInfo::new(2, 2, Mnemonic::Cpx, AddressingMode::Immediate)),
-        (0xE4, OpcodeInfo::new(2, 3, Mnemonic::Cpx, AddressingMode::ZeroPage )),
-        (0xEC, OpcodeInfo::new(3, 4, Mnemonic::Cpx, AddressingMode::Absolute )),
+    (0xC9, OpcodeInfo::new(2, 2, Mnemonic::Cmp, AddressingMode::Immediate)),
+    (0xC5, OpcodeInfo::new(2, 3, Mnemonic::Cmp, AddressingMode::ZeroPage )),
+    (0xD5, OpcodeInfo::new(2, 4, Mnemonic::Cmp, AddressingMode::ZeroPageX)),
+    (0xCD, OpcodeInfo::new(3, 4, Mnemonic::Cmp, AddressingMode::Absolute )),
+    (0xDD, OpcodeInfo::new(3, 4, Mnemonic::Cmp, AddressingMode::AbsoluteX)),
+    (0xD9, OpcodeInfo::new(3, 4, Mnemonic::Cmp, AddressingMode::AbsoluteY)),
+    (0xC1, OpcodeInfo::new(2, 6, Mnemonic::Cmp, AddressingMode::IndirectX)),
+    (0xD1, OpcodeInfo::new(2, 5, Mnemonic::Cmp, AddressingMode::IndirectY)),
 
-        (0xC0, OpcodeInfo::new(2, 2, Mnemonic::Cpy, AddressingMode::Immediate)),
-        (0xC4, OpcodeInfo::new(2, 3, Mnemonic::Cpy, AddressingMode::ZeroPage )),
-        (0xCC, OpcodeInfo::new(3, 4, Mnemonic::Cpy, AddressingMode::Absolute )),
+    (0xE0, OpcodeInfo::new(2, 2, Mnemonic::Cpx, AddressingMode::Immediate)),
+    (0xE4, OpcodeInfo::new(2, 3, Mnemonic::Cpx, AddressingMode::ZeroPage )),
+    (0xEC, OpcodeInfo::new(3, 4, Mnemonic::Cpx, AddressingMode::Absolute )),
 
-        (0xC6, OpcodeInfo::new(2, 5, Mnemonic::Dec, AddressingMode::ZeroPage )),
-        (0xD6, OpcodeInfo::new(2, 6, Mnemonic::Dec, AddressingMode::ZeroPageX)),
-        (0xCE, OpcodeInfo::new(3, 6, Mnemonic::Dec, AddressingMode::Absolute )),
-        (0xDE, OpcodeInfo::new(3, 7, Mnemonic::Dec, AddressingMode::AbsoluteX)),
+    (0xC0, OpcodeInfo::new(2, 2, Mnemonic::Cpy, AddressingMode::Immediate)),
+    (0xC4, OpcodeInfo::new(2, 3, Mnemonic::Cpy, AddressingMode::ZeroPage )),
+    (0xCC, OpcodeInfo::new(3, 4, Mnemonic::Cpy, AddressingMode::Absolute )),
 
-        (0xCA, OpcodeInfo::new(1, 2, Mnemonic::Dex, AddressingMode::Implied)),
+    (0xC6, OpcodeInfo::new(2, 5, Mnemonic::Dec, AddressingMode::ZeroPage )),
+    (0xD6, OpcodeInfo::new(2, 6, Mnemonic::Dec, AddressingMode::ZeroPageX)),
+    (0xCE, OpcodeInfo::new(3, 6, Mnemonic::Dec, AddressingMode::Absolute )),
+    (0xDE, OpcodeInfo::new(3, 7, Mnemonic::Dec, AddressingMode::AbsoluteX)),
 
-        (0x88, OpcodeInfo::new(1, 2, Mnemonic::Dey, AddressingMode::Implied)),
+    (0xCA, OpcodeInfo::new(1, 2, Mnemonic::Dex, AddressingMode::Implied)),
 
-        (0x49, OpcodeInfo::new(2, 2, Mnemonic::Eor, AddressingMode::Immediate)),
-        (0x45, OpcodeInfo::new(2, 3, Mnemonic::Eor, AddressingMode::ZeroPage )),
-        (0x55, OpcodeInfo::new(2, 4, Mnemonic::Eor, AddressingMode::ZeroPageX)),
-        (0x4D, OpcodeInfo::new(3, 4, Mnemonic::Eor, AddressingMode::Absolute )),
-        (0x5D, OpcodeInfo::new(3, 4, Mnemonic::Eor, AddressingMode::AbsoluteX)),
-        (0x59, OpcodeInfo::new(3, 4, Mnemonic::Eor, AddressingMode::AbsoluteY)),
-        (0x41, OpcodeInfo::new(2, 6, Mnemonic::Eor, AddressingMode::IndirectX)),
-        (0x51, OpcodeInfo::new(2, 5, Mnemonic::Eor, AddressingMode::IndirectY)),
+    (0x88, OpcodeInfo::new(1, 2, Mnemonic::Dey, AddressingMode::Implied)),
 
-        (0xE6, OpcodeInfo::new(2, 5, Mnemonic::Inc, AddressingMode::ZeroPage )),
-        (0xF6, OpcodeInfo::new(2, 6, Mnemonic::Inc, AddressingMode::ZeroPageX)),
-        (0xEE, OpcodeInfo::new(3, 6, Mnemonic::Inc, AddressingMode::Absolute )),
-        (0xFE, OpcodeInfo::new(3, 7, Mnemonic::Inc, AddressingMode::AbsoluteX)),
+    (0x49, OpcodeInfo::new(2, 2, Mnemonic::Eor, AddressingMode::Immediate)),
+    (0x45, OpcodeInfo::new(2, 3, Mnemonic::Eor, AddressingMode::ZeroPage )),
+    (0x55, OpcodeInfo::new(2, 4, Mnemonic::Eor, AddressingMode::ZeroPageX)),
+    (0x4D, OpcodeInfo::new(3, 4, Mnemonic::Eor, AddressingMode::Absolute )),
+    (0x5D, OpcodeInfo::new(3, 4, Mnemonic::Eor, AddressingMode::AbsoluteX)),
+    (0x59, OpcodeInfo::new(3, 4, Mnemonic::Eor, AddressingMode::AbsoluteY)),
+    (0x41, OpcodeInfo::new(2, 6, Mnemonic::Eor, AddressingMode::IndirectX)),
+    (0x51, OpcodeInfo::new(2, 5, Mnemonic::Eor, AddressingMode::IndirectY)),
 
-        (0xE8, OpcodeInfo::new(1, 2, Mnemonic::Inx, AddressingMode::Implied)),
+    (0xE6, OpcodeInfo::new(2, 5, Mnemonic::Inc, AddressingMode::ZeroPage )),
+    (0xF6, OpcodeInfo::new(2, 6, Mnemonic::Inc, AddressingMode::ZeroPageX)),
+    (0xEE, OpcodeInfo::new(3, 6, Mnemonic::Inc, AddressingMode::Absolute )),
+    (0xFE, OpcodeInfo::new(3, 7, Mnemonic::Inc, AddressingMode::AbsoluteX)),
 
-        (0xC8, OpcodeInfo::new(1, 2, Mnemonic::Iny, AddressingMode::Implied)),
+    (0xE8, OpcodeInfo::new(1, 2, Mnemonic::Inx, AddressingMode::Implied)),
 
-        (0x4C, OpcodeInfo::new(3, 3, Mnemonic::Jmp, AddressingMode::Absolute)),
-        (0x6C, OpcodeInfo::new(3, 5, Mnemonic::Jmp, AddressingMode::Indirect)),
+    (0xC8, OpcodeInfo::new(1, 2, Mnemonic::Iny, AddressingMode::Implied)),
 
-        (0x20, OpcodeInfo::new(3, 6, Mnemonic::Jsr, AddressingMode::Absolute)),
+    (0x4C, OpcodeInfo::new(3, 3, Mnemonic::Jmp, AddressingMode::Absolute)),
+    (0x6C, OpcodeInfo::new(3, 5, Mnemonic::Jmp, AddressingMode::Indirect)),
 
-        (0xA9, OpcodeInfo::new(2, 2, Mnemonic::Lda, AddressingMode::Immediate)),
-        (0xA5, OpcodeInfo::new(2, 3, Mnemonic::Lda, AddressingMode::ZeroPage )),
-        (0xB5, OpcodeInfo::new(2, 4, Mnemonic::Lda, AddressingMode::ZeroPageX)),
-        (0xAD, OpcodeInfo::new(3, 4, Mnemonic::Lda, AddressingMode::Absolute )),
-        (0xBD, OpcodeInfo::new(3, 4, Mnemonic::Lda, AddressingMode::AbsoluteX)),
-        (0xB9, OpcodeInfo::new(3, 4, Mnemonic::Lda, AddressingMode::AbsoluteY)),
-        (0xA1, OpcodeInfo::new(2, 6, Mnemonic::Lda, AddressingMode::IndirectX)),
-        (0xB1, OpcodeInfo::new(2, 5, Mnemonic::Lda, AddressingMode::IndirectY)),
+    (0x20, OpcodeInfo::new(3, 6, Mnemonic::Jsr, AddressingMode::Absolute)),
 
-        (0xA2, OpcodeInfo::new(2, 2, Mnemonic::Ldx, AddressingMode::Immediate)),
-        (0xA6, OpcodeInfo::new(2, 3, Mnemonic::Ldx, AddressingMode::ZeroPage )),
-        (0xB6, OpcodeInfo::new(2, 4, Mnemonic::Ldx, AddressingMode::ZeroPageY)),
-        (0xAE, OpcodeInfo::new(3, 4, Mnemonic::Ldx, AddressingMode::Absolute )),
-        (0xBE, OpcodeInfo::new(3, 4, Mnemonic::Ldx, AddressingMode::AbsoluteY)),
+    (0xA9, OpcodeInfo::new(2, 2, Mnemonic::Lda, AddressingMode::Immediate)),
+    (0xA5, OpcodeInfo::new(2, 3, Mnemonic::Lda, AddressingMode::ZeroPage )),
+    (0xB5, OpcodeInfo::new(2, 4, Mnemonic::Lda, AddressingMode::ZeroPageX)),
+    (0xAD, OpcodeInfo::new(3, 4, Mnemonic::Lda, AddressingMode::Absolute )),
+    (0xBD, OpcodeInfo::new(3, 4, Mnemonic::Lda, AddressingMode::AbsoluteX)),
+    (0xB9, OpcodeInfo::new(3, 4, Mnemonic::Lda, AddressingMode::AbsoluteY)),
+    (0xA1, OpcodeInfo::new(2, 6, Mnemonic::Lda, AddressingMode::IndirectX)),
+    (0xB1, OpcodeInfo::new(2, 5, Mnemonic::Lda, AddressingMode::IndirectY)),
 
-        (0xA0, OpcodeInfo::new(2, 2, Mnemonic::Ldy, AddressingMode::Immediate)),
-        (0xA4, OpcodeInfo::new(2, 3, Mnemonic::Ldy, AddressingMode::ZeroPage )),
-        (0xB4, OpcodeInfo::new(2, 4, Mnemonic::Ldy, AddressingMode::ZeroPageX)),
-        (0xAC, OpcodeInfo::new(3, 4, Mnemonic::Ldy, AddressingMode::Absolute )),
-        (0xBC, OpcodeInfo::new(3, 4, Mnemonic::Ldy, AddressingMode::AbsoluteX)),
+    (0xA2, OpcodeInfo::new(2, 2, Mnemonic::Ldx, AddressingMode::Immediate)),
+    (0xA6, OpcodeInfo::new(2, 3, Mnemonic::Ldx, AddressingMode::ZeroPage )),
+    (0xB6, OpcodeInfo::new(2, 4, Mnemonic::Ldx, AddressingMode::ZeroPageY)),
+    (0xAE, OpcodeInfo::new(3, 4, Mnemonic::Ldx, AddressingMode::Absolute )),
+    (0xBE, OpcodeInfo::new(3, 4, Mnemonic::Ldx, AddressingMode::AbsoluteY)),
 
-        (0x4A, OpcodeInfo::new(1, 2, Mnemonic::Lsr, AddressingMode::Accumulator)),
-        (0x46, OpcodeInfo::new(2, 5, Mnemonic::Lsr, AddressingMode::ZeroPage   )),
-        (0x56, OpcodeInfo::new(2, 6, Mnemonic::Lsr, AddressingMode::ZeroPageX  )),
-        (0x4E, OpcodeInfo::new(3, 6, Mnemonic::Lsr, AddressingMode::Absolute   )),
-        (0x5E, OpcodeInfo::new(3, 7, Mnemonic::Lsr, AddressingMode::AbsoluteX  )),
+    (0xA0, OpcodeInfo::new(2, 2, Mnemonic::Ldy, AddressingMode::Immediate)),
+    (0xA4, OpcodeInfo::new(2, 3, Mnemonic::Ldy, AddressingMode::ZeroPage )),
+    (0xB4, OpcodeInfo::new(2, 4, Mnemonic::Ldy, AddressingMode::ZeroPageX)),
+    (0xAC, OpcodeInfo::new(3, 4, Mnemonic::Ldy, AddressingMode::Absolute )),
+    (0xBC, OpcodeInfo::new(3, 4, Mnemonic::Ldy, AddressingMode::AbsoluteX)),
 
-        (0xEA, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied)),
+    (0x4A, OpcodeInfo::new(1, 2, Mnemonic::Lsr, AddressingMode::Accumulator)),
+    (0x46, OpcodeInfo::new(2, 5, Mnemonic::Lsr, AddressingMode::ZeroPage   )),
+    (0x56, OpcodeInfo::new(2, 6, Mnemonic::Lsr, AddressingMode::ZeroPageX  )),
+    (0x4E, OpcodeInfo::new(3, 6, Mnemonic::Lsr, AddressingMode::Absolute   )),
+    (0x5E, OpcodeInfo::new(3, 7, Mnemonic::Lsr, AddressingMode::AbsoluteX  )),
 
-        (0x09, OpcodeInfo::new(2, 2, Mnemonic::Ora, AddressingMode::Immediate)),
-        (0x05, OpcodeInfo::new(2, 3, Mnemonic::Ora, AddressingMode::ZeroPage )),
-        (0x15, OpcodeInfo::new(2, 4, Mnemonic::Ora, AddressingMode::ZeroPageX)),
-        (0x0D, OpcodeInfo::new(3, 4, Mnemonic::Ora, AddressingMode::Absolute )),
-        (0x1D, OpcodeInfo::new(3, 4, Mnemonic::Ora, AddressingMode::AbsoluteX)),
-        (0x19, OpcodeInfo::new(3, 4, Mnemonic::Ora, AddressingMode::AbsoluteY)),
-        (0x01, OpcodeInfo::new(2, 6, Mnemonic::Ora, AddressingMode::IndirectX)),
-        (0x11, OpcodeInfo::new(2, 5, Mnemonic::Ora, AddressingMode::IndirectY)),
+    (0xEA, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied)),
 
-        (0x48, OpcodeInfo::new(1, 3, Mnemonic::Pha, AddressingMode::Implied)),
+    (0x09, OpcodeInfo::new(2, 2, Mnemonic::Ora, AddressingMode::Immediate)),
+    (0x05, OpcodeInfo::new(2, 3, Mnemonic::Ora, AddressingMode::ZeroPage )),
+    (0x15, OpcodeInfo::new(2, 4, Mnemonic::Ora, AddressingMode::ZeroPageX)),
+    (0x0D, OpcodeInfo::new(3, 4, Mnemonic::Ora, AddressingMode::Absolute )),
+    (0x1D, OpcodeInfo::new(3, 4, Mnemonic::Ora, AddressingMode::AbsoluteX)),
+    (0x19, OpcodeInfo::new(3, 4, Mnemonic::Ora, AddressingMode::AbsoluteY)),
+    (0x01, OpcodeInfo::new(2, 6, Mnemonic::Ora, AddressingMode::IndirectX)),
+    (0x11, OpcodeInfo::new(2, 5, Mnemonic::Ora, AddressingMode::IndirectY)),
 
-        (0x08, OpcodeInfo::new(1, 3, Mnemonic::Php, AddressingMode::Implied)),
+    (0x48, OpcodeInfo::new(1, 3, Mnemonic::Pha, AddressingMode::Implied)),
 
-        (0x68, OpcodeInfo::new(1, 4, Mnemonic::Pla, AddressingMode::Implied)),
+    (0x08, OpcodeInfo::new(1, 3, Mnemonic::Php, AddressingMode::Implied)),
 
-        (0x28, OpcodeInfo::new(1, 4, Mnemonic::Plp, AddressingMode::Implied)),
+    (0x68, OpcodeInfo::new(1, 4, Mnemonic::Pla, AddressingMode::Implied)),
 
-        (0x2A, OpcodeInfo::new(1, 2, Mnemonic::Rol, AddressingMode::Accumulator)),
-        (0x26, OpcodeInfo::new(2, 5, Mnemonic::Rol, AddressingMode::ZeroPage   )),
-        (0x36, OpcodeInfo::new(2, 6, Mnemonic::Rol, AddressingMode::ZeroPageX  )),
-        (0x2E, OpcodeInfo::new(3, 6, Mnemonic::Rol, AddressingMode::Absolute   )),
-        (0x3E, OpcodeInfo::new(3, 7, Mnemonic::Rol, AddressingMode::AbsoluteX  )),
+    (0x28, OpcodeInfo::new(1, 4, Mnemonic::Plp, AddressingMode::Implied)),
 
-        (0x6A, OpcodeInfo::new(1, 2, Mnemonic::Ror, AddressingMode::Accumulator)),
-        (0x66, OpcodeInfo::new(2, 5, Mnemonic::Ror, AddressingMode::ZeroPage   )),
-        (0x76, OpcodeInfo::new(2, 6, Mnemonic::Ror, AddressingMode::ZeroPageX  )),
-        (0x6E, OpcodeInfo::new(3, 6, Mnemonic::Ror, AddressingMode::Absolute   )),
-        (0x7E, OpcodeInfo::new(3, 7, Mnemonic::Ror, AddressingMode::AbsoluteX  )),
+    (0x2A, OpcodeInfo::new(1, 2, Mnemonic::Rol, AddressingMode::Accumulator)),
+    (0x26, OpcodeInfo::new(2, 5, Mnemonic::Rol, AddressingMode::ZeroPage   )),
+    (0x36, OpcodeInfo::new(2, 6, Mnemonic::Rol, AddressingMode::ZeroPageX  )),
+    (0x2E, OpcodeInfo::new(3, 6, Mnemonic::Rol, AddressingMode::Absolute   )),
+    (0x3E, OpcodeInfo::new(3, 7, Mnemonic::Rol, AddressingMode::AbsoluteX  )),
 
-        (0x40, OpcodeInfo::new(1, 6, Mnemonic::Rti, AddressingMode::Implied)),
+    (0x6A, OpcodeInfo::new(1, 2, Mnemonic::Ror, AddressingMode::Accumulator)),
+    (0x66, OpcodeInfo::new(2, 5, Mnemonic::Ror, AddressingMode::ZeroPage   )),
+    (0x76, OpcodeInfo::new(2, 6, Mnemonic::Ror, AddressingMode::ZeroPageX  )),
+    (0x6E, OpcodeInfo::new(3, 6, Mnemonic::Ror, AddressingMode::Absolute   )),
+    (0x7E, OpcodeInfo::new(3, 7, Mnemonic::Ror, AddressingMode::AbsoluteX  )),
 
-        (0x60, OpcodeInfo::new(1, 6, Mnemonic::Rts, AddressingMode::Implied)),
+    (0x40, OpcodeInfo::new(1, 6, Mnemonic::Rti, AddressingMode::Implied)),
 
-        (0xE9, OpcodeInfo::new(2, 2, Mnemonic::Sbc, AddressingMode::Immediate)),
-        (0xE5, OpcodeInfo::new(2, 3, Mnemonic::Sbc, AddressingMode::ZeroPage )),
-        (0xF5, OpcodeInfo::new(2, 4, Mnemonic::Sbc, AddressingMode::ZeroPageX)),
-        (0xED, OpcodeInfo::new(3, 4, Mnemonic::Sbc, AddressingMode::Absolute )),
-        (0xFD, OpcodeInfo::new(3, 4, Mnemonic::Sbc, AddressingMode::AbsoluteX)),
-        (0xF9, OpcodeInfo::new(3, 4, Mnemonic::Sbc, AddressingMode::AbsoluteY)),
-        (0xE1, OpcodeInfo::new(2, 6, Mnemonic::Sbc, AddressingMode::IndirectX)),
-        (0xF1, OpcodeInfo::new(2, 5, Mnemonic::Sbc, AddressingMode::IndirectY)),
+    (0x60, OpcodeInfo::new(1, 6, Mnemonic::Rts, AddressingMode::Implied)),
 
-        (0x38, OpcodeInfo::new(1, 2, Mnemonic::Sec, AddressingMode::Implied)),
+    (0xE9, OpcodeInfo::new(2, 2, Mnemonic::Sbc, AddressingMode::Immediate)),
+    (0xE5, OpcodeInfo::new(2, 3, Mnemonic::Sbc, AddressingMode::ZeroPage )),
+    (0xF5, OpcodeInfo::new(2, 4, Mnemonic::Sbc, AddressingMode::ZeroPageX)),
+    (0xED, OpcodeInfo::new(3, 4, Mnemonic::Sbc, AddressingMode::Absolute )),
+    (0xFD, OpcodeInfo::new(3, 4, Mnemonic::Sbc, AddressingMode::AbsoluteX)),
+    (0xF9, OpcodeInfo::new(3, 4, Mnemonic::Sbc, AddressingMode::AbsoluteY)),
+    (0xE1, OpcodeInfo::new(2, 6, Mnemonic::Sbc, AddressingMode::IndirectX)),
+    (0xF1, OpcodeInfo::new(2, 5, Mnemonic::Sbc, AddressingMode::IndirectY)),
 
-        (0xF8, OpcodeInfo::new(1, 2, Mnemonic::Sed, AddressingMode::Implied)),
+    (0x38, OpcodeInfo::new(1, 2, Mnemonic::Sec, AddressingMode::Implied)),
 
-        (0x78, OpcodeInfo::new(1, 2, Mnemonic::Sei, AddressingMode::Implied)),
+    (0xF8, OpcodeInfo::new(1, 2, Mnemonic::Sed, AddressingMode::Implied)),
 
-        (0x85, OpcodeInfo::new(2, 3, Mnemonic::Sta, AddressingMode::ZeroPage )),
-        (0x95, OpcodeInfo::new(2, 4, Mnemonic::Sta, AddressingMode::ZeroPageX)),
-        (0x8D, OpcodeInfo::new(3, 4, Mnemonic::Sta, AddressingMode::Absolute )),
-        (0x9D, OpcodeInfo::new(3, 4, Mnemonic::Sta, AddressingMode::AbsoluteX)),
-        (0x99, OpcodeInfo::new(3, 4, Mnemonic::Sta, AddressingMode::AbsoluteY)),
-        (0x81, OpcodeInfo::new(2, 6, Mnemonic::Sta, AddressingMode::IndirectX)),
-        (0x91, OpcodeInfo::new(2, 5, Mnemonic::Sta, AddressingMode::IndirectY)),
+    (0x78, OpcodeInfo::new(1, 2, Mnemonic::Sei, AddressingMode::Implied)),
 
-        (0x86, OpcodeInfo::new(2, 3, Mnemonic::Stx, AddressingMode::ZeroPage )),
-        (0x96, OpcodeInfo::new(2, 4, Mnemonic::Stx, AddressingMode::ZeroPageY)),
-        (0x8E, OpcodeInfo::new(3, 4, Mnemonic::Stx, AddressingMode::Absolute )),
+    (0x85, OpcodeInfo::new(2, 3, Mnemonic::Sta, AddressingMode::ZeroPage )),
+    (0x95, OpcodeInfo::new(2, 4, Mnemonic::Sta, AddressingMode::ZeroPageX)),
+    (0x8D, OpcodeInfo::new(3, 4, Mnemonic::Sta, AddressingMode::Absolute )),
+    (0x9D, OpcodeInfo::new(3, 5, Mnemonic::Sta, AddressingMode::AbsoluteX)),
+    (0x99, OpcodeInfo::new(3, 5, Mnemonic::Sta, AddressingMode::AbsoluteY)),
+    (0x81, OpcodeInfo::new(2, 6, Mnemonic::Sta, AddressingMode::IndirectX)),
+    (0x91, OpcodeInfo::new(2, 6, Mnemonic::Sta, AddressingMode::IndirectY)),
 
-        (0x84, OpcodeInfo::new(2, 3, Mnemonic::Sty, AddressingMode::ZeroPage )),
-        (0x94, OpcodeInfo::new(2, 4, Mnemonic::Sty, AddressingMode::ZeroPageX)),
-        (0x8C, OpcodeInfo::new(3, 4, Mnemonic::Sty, AddressingMode::Absolute )),
+    (0x86, OpcodeInfo::new(2, 3, Mnemonic::Stx, AddressingMode::ZeroPage )),
+    (0x96, OpcodeInfo::new(2, 4, Mnemonic::Stx, AddressingMode::ZeroPageY)),
+    (0x8E, OpcodeInfo::new(3, 4, Mnemonic::Stx, AddressingMode::Absolute )),
 
-        (0xAA, OpcodeInfo::new(1, 2, Mnemonic::Tax, AddressingMode::Implied)),
+    (0x84, OpcodeInfo::new(2, 3, Mnemonic::Sty, AddressingMode::ZeroPage )),
+    (0x94, OpcodeInfo::new(2, 4, Mnemonic::Sty, AddressingMode::ZeroPageX)),
+    (0x8C, OpcodeInfo::new(3, 4, Mnemonic::Sty, AddressingMode::Absolute )),
 
-        (0xA8, OpcodeInfo::new(1, 2, Mnemonic::Tay, AddressingMode::Implied)),
+    (0xAA, OpcodeInfo::new(1, 2, Mnemonic::Tax, AddressingMode::Implied)),
 
-        (0xBA, OpcodeInfo::new(1, 2, Mnemonic::Tsx, AddressingMode::Implied)),
+    (0xA8, OpcodeInfo::new(1, 2, Mnemonic::Tay, AddressingMode::Implied)),
 
-        (0x8A, OpcodeInfo::new(1, 2, Mnemonic::Txa, AddressingMode::Implied)),
+    (0xBA, OpcodeInfo::new(1, 2, Mnemonic::Tsx, AddressingMode::Implied)),
 
-        (0x9A, OpcodeInfo::new(1, 2, Mnemonic::Txs, AddressingMode::Implied)),
+    (0x8A, OpcodeInfo::new(1, 2, Mnemonic::Txa, AddressingMode::Implied)),
 
-        (0x98, OpcodeInfo::new(1, 2, Mnemonic::Tya, AddressingMode::Implied)),
-    ])
-});
+    (0x9A, OpcodeInfo::new(1, 2, Mnemonic::Txs, AddressingMode::Implied)),
+
+    (0x98, OpcodeInfo::new(1, 2, Mnemonic::Tya, AddressingMode::Implied)),
+]);
+
+/// Opcodes that are not documented, but real NMOS 6502 executes.
+///
+/// Some of them (Ahx, Lxa, Shx, Shy, Tas and Xaa) are unstable on real hardware.
+/// These are implemented with the commonly observed behavior.
+pub static UNOFFICIAL_OPCODE_TABLE: OpcodeTable = build_table(&[
+    (0x93, OpcodeInfo::new(2, 6, Mnemonic::Ahx, AddressingMode::IndirectY)),
+    (0x9F, OpcodeInfo::new(3, 5, Mnemonic::Ahx, AddressingMode::AbsoluteY)),
+
+    (0x4B, OpcodeInfo::new(2, 2, Mnemonic::Alr, AddressingMode::Immediate)),
+
+    (0x0B, OpcodeInfo::new(2, 2, Mnemonic::Anc, AddressingMode::Immediate)),
+    (0x2B, OpcodeInfo::new(2, 2, Mnemonic::Anc, AddressingMode::Immediate)),
+
+    (0x6B, OpcodeInfo::new(2, 2, Mnemonic::Arr, AddressingMode::Immediate)),
+
+    (0xCB, OpcodeInfo::new(2, 2, Mnemonic::Axs, AddressingMode::Immediate)),
+
+    (0xC7, OpcodeInfo::new(2, 5, Mnemonic::Dcp, AddressingMode::ZeroPage )),
+    (0xD7, OpcodeInfo::new(2, 6, Mnemonic::Dcp, AddressingMode::ZeroPageX)),
+    (0xCF, OpcodeInfo::new(3, 6, Mnemonic::Dcp, AddressingMode::Absolute )),
+    (0xDF, OpcodeInfo::new(3, 7, Mnemonic::Dcp, AddressingMode::AbsoluteX)),
+    (0xDB, OpcodeInfo::new(3, 7, Mnemonic::Dcp, AddressingMode::AbsoluteY)),
+    (0xC3, OpcodeInfo::new(2, 8, Mnemonic::Dcp, AddressingMode::IndirectX)),
+    (0xD3, OpcodeInfo::new(2, 8, Mnemonic::Dcp, AddressingMode::IndirectY)),
+
+    (0xE7, OpcodeInfo::new(2, 5, Mnemonic::Isc, AddressingMode::ZeroPage )),
+    (0xF7, OpcodeInfo::new(2, 6, Mnemonic::Isc, AddressingMode::ZeroPageX)),
+    (0xEF, OpcodeInfo::new(3, 6, Mnemonic::Isc, AddressingMode::Absolute )),
+    (0xFF, OpcodeInfo::new(3, 7, Mnemonic::Isc, AddressingMode::AbsoluteX)),
+    (0xFB, OpcodeInfo::new(3, 7, Mnemonic::Isc, AddressingMode::AbsoluteY)),
+    (0xE3, OpcodeInfo::new(2, 8, Mnemonic::Isc, AddressingMode::IndirectX)),
+    (0xF3, OpcodeInfo::new(2, 8, Mnemonic::Isc, AddressingMode::IndirectY)),
+
+    (0x02, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x12, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x22, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x32, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x42, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x52, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x62, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x72, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0x92, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0xB2, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0xD2, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+    (0xF2, OpcodeInfo::new(1, 2, Mnemonic::Kil, AddressingMode::Implied)),
+
+    (0xBB, OpcodeInfo::new(3, 4, Mnemonic::Las, AddressingMode::AbsoluteY)),
+
+    (0xA7, OpcodeInfo::new(2, 3, Mnemonic::Lax, AddressingMode::ZeroPage )),
+    (0xB7, OpcodeInfo::new(2, 4, Mnemonic::Lax, AddressingMode::ZeroPageY)),
+    (0xAF, OpcodeInfo::new(3, 4, Mnemonic::Lax, AddressingMode::Absolute )),
+    (0xBF, OpcodeInfo::new(3, 4, Mnemonic::Lax, AddressingMode::AbsoluteY)),
+    (0xA3, OpcodeInfo::new(2, 6, Mnemonic::Lax, AddressingMode::IndirectX)),
+    (0xB3, OpcodeInfo::new(2, 5, Mnemonic::Lax, AddressingMode::IndirectY)),
+
+    (0xAB, OpcodeInfo::new(2, 2, Mnemonic::Lxa, AddressingMode::Immediate)),
+
+    (0x1A, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x3A, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x5A, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x7A, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xDA, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xFA, OpcodeInfo::new(1, 2, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x80, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x82, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x89, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0xC2, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0xE2, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x04, OpcodeInfo::new(2, 3, Mnemonic::Nop, AddressingMode::ZeroPage )),
+    (0x44, OpcodeInfo::new(2, 3, Mnemonic::Nop, AddressingMode::ZeroPage )),
+    (0x64, OpcodeInfo::new(2, 3, Mnemonic::Nop, AddressingMode::ZeroPage )),
+    (0x14, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0x34, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0x54, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0x74, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0xD4, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0xF4, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0x0C, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::Absolute )),
+    (0x1C, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::AbsoluteX)),
+    (0x3C, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::AbsoluteX)),
+    (0x5C, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::AbsoluteX)),
+    (0x7C, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::AbsoluteX)),
+    (0xDC, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::AbsoluteX)),
+    (0xFC, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::AbsoluteX)),
+
+    (0x27, OpcodeInfo::new(2, 5, Mnemonic::Rla, AddressingMode::ZeroPage )),
+    (0x37, OpcodeInfo::new(2, 6, Mnemonic::Rla, AddressingMode::ZeroPageX)),
+    (0x2F, OpcodeInfo::new(3, 6, Mnemonic::Rla, AddressingMode::Absolute )),
+    (0x3F, OpcodeInfo::new(3, 7, Mnemonic::Rla, AddressingMode::AbsoluteX)),
+    (0x3B, OpcodeInfo::new(3, 7, Mnemonic::Rla, AddressingMode::AbsoluteY)),
+    (0x23, OpcodeInfo::new(2, 8, Mnemonic::Rla, AddressingMode::IndirectX)),
+    (0x33, OpcodeInfo::new(2, 8, Mnemonic::Rla, AddressingMode::IndirectY)),
+
+    (0x67, OpcodeInfo::new(2, 5, Mnemonic::Rra, AddressingMode::ZeroPage )),
+    (0x77, OpcodeInfo::new(2, 6, Mnemonic::Rra, AddressingMode::ZeroPageX)),
+    (0x6F, OpcodeInfo::new(3, 6, Mnemonic::Rra, AddressingMode::Absolute )),
+    (0x7F, OpcodeInfo::new(3, 7, Mnemonic::Rra, AddressingMode::AbsoluteX)),
+    (0x7B, OpcodeInfo::new(3, 7, Mnemonic::Rra, AddressingMode::AbsoluteY)),
+    (0x63, OpcodeInfo::new(2, 8, Mnemonic::Rra, AddressingMode::IndirectX)),
+    (0x73, OpcodeInfo::new(2, 8, Mnemonic::Rra, AddressingMode::IndirectY)),
+
+    (0x87, OpcodeInfo::new(2, 3, Mnemonic::Sax, AddressingMode::ZeroPage )),
+    (0x97, OpcodeInfo::new(2, 4, Mnemonic::Sax, AddressingMode::ZeroPageY)),
+    (0x8F, OpcodeInfo::new(3, 4, Mnemonic::Sax, AddressingMode::Absolute )),
+    (0x83, OpcodeInfo::new(2, 6, Mnemonic::Sax, AddressingMode::IndirectX)),
+
+    (0xEB, OpcodeInfo::new(2, 2, Mnemonic::Sbc, AddressingMode::Immediate)),
+
+    (0x9E, OpcodeInfo::new(3, 5, Mnemonic::Shx, AddressingMode::AbsoluteY)),
+
+    (0x9C, OpcodeInfo::new(3, 5, Mnemonic::Shy, AddressingMode::AbsoluteX)),
+
+    (0x07, OpcodeInfo::new(2, 5, Mnemonic::Slo, AddressingMode::ZeroPage )),
+    (0x17, OpcodeInfo::new(2, 6, Mnemonic::Slo, AddressingMode::ZeroPageX)),
+    (0x0F, OpcodeInfo::new(3, 6, Mnemonic::Slo, AddressingMode::Absolute )),
+    (0x1F, OpcodeInfo::new(3, 7, Mnemonic::Slo, AddressingMode::AbsoluteX)),
+    (0x1B, OpcodeInfo::new(3, 7, Mnemonic::Slo, AddressingMode::AbsoluteY)),
+    (0x03, OpcodeInfo::new(2, 8, Mnemonic::Slo, AddressingMode::IndirectX)),
+    (0x13, OpcodeInfo::new(2, 8, Mnemonic::Slo, AddressingMode::IndirectY)),
+
+    (0x47, OpcodeInfo::new(2, 5, Mnemonic::Sre, AddressingMode::ZeroPage )),
+    (0x57, OpcodeInfo::new(2, 6, Mnemonic::Sre, AddressingMode::ZeroPageX)),
+    (0x4F, OpcodeInfo::new(3, 6, Mnemonic::Sre, AddressingMode::Absolute )),
+    (0x5F, OpcodeInfo::new(3, 7, Mnemonic::Sre, AddressingMode::AbsoluteX)),
+    (0x5B, OpcodeInfo::new(3, 7, Mnemonic::Sre, AddressingMode::AbsoluteY)),
+    (0x43, OpcodeInfo::new(2, 8, Mnemonic::Sre, AddressingMode::IndirectX)),
+    (0x53, OpcodeInfo::new(2, 8, Mnemonic::Sre, AddressingMode::IndirectY)),
+
+    (0x9B, OpcodeInfo::new(3, 5, Mnemonic::Tas, AddressingMode::AbsoluteY)),
+
+    (0x8B, OpcodeInfo::new(2, 2, Mnemonic::Xaa, AddressingMode::Immediate)),
+]);
+
+/// Opcodes that WDC 65C02 adds to or changes from OPCODE_TABLE.
+///
+/// Opcodes not in OPCODE_TABLE or this table don't exist on 65C02.
+/// All of them are listed here as Nop, as 65C02 executes them as no operation.
+pub static CMOS_OPCODE_TABLE: OpcodeTable = build_table(&[
+    (0x72, OpcodeInfo::new(2, 5, Mnemonic::Adc, AddressingMode::ZeroPageIndirect)),
+
+    (0x32, OpcodeInfo::new(2, 5, Mnemonic::And, AddressingMode::ZeroPageIndirect)),
+
+    (0x1E, OpcodeInfo::new(3, 6, Mnemonic::Asl, AddressingMode::AbsoluteX)),
+
+    (0x89, OpcodeInfo::new(2, 2, Mnemonic::Bit, AddressingMode::Immediate)),
+    (0x34, OpcodeInfo::new(2, 4, Mnemonic::Bit, AddressingMode::ZeroPageX)),
+    (0x3C, OpcodeInfo::new(3, 4, Mnemonic::Bit, AddressingMode::AbsoluteX)),
+
+    (0x80, OpcodeInfo::new(2, 2, Mnemonic::Bra, AddressingMode::Relative)),
+
+    (0xD2, OpcodeInfo::new(2, 5, Mnemonic::Cmp, AddressingMode::ZeroPageIndirect)),
+
+    (0x3A, OpcodeInfo::new(1, 2, Mnemonic::Dec, AddressingMode::Accumulator)),
+
+    (0x52, OpcodeInfo::new(2, 5, Mnemonic::Eor, AddressingMode::ZeroPageIndirect)),
+
+    (0x1A, OpcodeInfo::new(1, 2, Mnemonic::Inc, AddressingMode::Accumulator)),
+
+    (0x6C, OpcodeInfo::new(3, 6, Mnemonic::Jmp, AddressingMode::Indirect         )),
+    (0x7C, OpcodeInfo::new(3, 6, Mnemonic::Jmp, AddressingMode::AbsoluteIndirectX)),
+
+    (0xB2, OpcodeInfo::new(2, 5, Mnemonic::Lda, AddressingMode::ZeroPageIndirect)),
+
+    (0x5E, OpcodeInfo::new(3, 6, Mnemonic::Lsr, AddressingMode::AbsoluteX)),
+
+    (0x12, OpcodeInfo::new(2, 5, Mnemonic::Ora, AddressingMode::ZeroPageIndirect)),
+
+    (0xDA, OpcodeInfo::new(1, 3, Mnemonic::Phx, AddressingMode::Implied)),
+
+    (0x5A, OpcodeInfo::new(1, 3, Mnemonic::Phy, AddressingMode::Implied)),
+
+    (0xFA, OpcodeInfo::new(1, 4, Mnemonic::Plx, AddressingMode::Implied)),
+
+    (0x7A, OpcodeInfo::new(1, 4, Mnemonic::Ply, AddressingMode::Implied)),
+
+    (0x3E, OpcodeInfo::new(3, 6, Mnemonic::Rol, AddressingMode::AbsoluteX)),
+
+    (0x7E, OpcodeInfo::new(3, 6, Mnemonic::Ror, AddressingMode::AbsoluteX)),
+
+    (0xF2, OpcodeInfo::new(2, 5, Mnemonic::Sbc, AddressingMode::ZeroPageIndirect)),
+
+    (0x92, OpcodeInfo::new(2, 5, Mnemonic::Sta, AddressingMode::ZeroPageIndirect)),
+
+    (0xDB, OpcodeInfo::new(1, 3, Mnemonic::Stp, AddressingMode::Implied)),
+
+    (0x64, OpcodeInfo::new(2, 3, Mnemonic::Stz, AddressingMode::ZeroPage )),
+    (0x74, OpcodeInfo::new(2, 4, Mnemonic::Stz, AddressingMode::ZeroPageX)),
+    (0x9C, OpcodeInfo::new(3, 4, Mnemonic::Stz, AddressingMode::Absolute )),
+    (0x9E, OpcodeInfo::new(3, 5, Mnemonic::Stz, AddressingMode::AbsoluteX)),
+
+    (0x14, OpcodeInfo::new(2, 5, Mnemonic::Trb, AddressingMode::ZeroPage)),
+    (0x1C, OpcodeInfo::new(3, 6, Mnemonic::Trb, AddressingMode::Absolute)),
+
+    (0x04, OpcodeInfo::new(2, 5, Mnemonic::Tsb, AddressingMode::ZeroPage)),
+    (0x0C, OpcodeInfo::new(3, 6, Mnemonic::Tsb, AddressingMode::Absolute)),
+
+    (0xCB, OpcodeInfo::new(1, 3, Mnemonic::Wai, AddressingMode::Implied)),
+
+    (0x07, OpcodeInfo::new(2, 5, Mnemonic::Rmb0, AddressingMode::ZeroPage        )),
+    (0x87, OpcodeInfo::new(2, 5, Mnemonic::Smb0, AddressingMode::ZeroPage        )),
+    (0x0F, OpcodeInfo::new(3, 5, Mnemonic::Bbr0, AddressingMode::ZeroPageRelative)),
+    (0x8F, OpcodeInfo::new(3, 5, Mnemonic::Bbs0, AddressingMode::ZeroPageRelative)),
+    (0x17, OpcodeInfo::new(2, 5, Mnemonic::Rmb1, AddressingMode::ZeroPage        )),
+    (0x97, OpcodeInfo::new(2, 5, Mnemonic::Smb1, AddressingMode::ZeroPage        )),
+    (0x1F, OpcodeInfo::new(3, 5, Mnemonic::Bbr1, AddressingMode::ZeroPageRelative)),
+    (0x9F, OpcodeInfo::new(3, 5, Mnemonic::Bbs1, AddressingMode::ZeroPageRelative)),
+    (0x27, OpcodeInfo::new(2, 5, Mnemonic::Rmb2, AddressingMode::ZeroPage        )),
+    (0xA7, OpcodeInfo::new(2, 5, Mnemonic::Smb2, AddressingMode::ZeroPage        )),
+    (0x2F, OpcodeInfo::new(3, 5, Mnemonic::Bbr2, AddressingMode::ZeroPageRelative)),
+    (0xAF, OpcodeInfo::new(3, 5, Mnemonic::Bbs2, AddressingMode::ZeroPageRelative)),
+    (0x37, OpcodeInfo::new(2, 5, Mnemonic::Rmb3, AddressingMode::ZeroPage        )),
+    (0xB7, OpcodeInfo::new(2, 5, Mnemonic::Smb3, AddressingMode::ZeroPage        )),
+    (0x3F, OpcodeInfo::new(3, 5, Mnemonic::Bbr3, AddressingMode::ZeroPageRelative)),
+    (0xBF, OpcodeInfo::new(3, 5, Mnemonic::Bbs3, AddressingMode::ZeroPageRelative)),
+    (0x47, OpcodeInfo::new(2, 5, Mnemonic::Rmb4, AddressingMode::ZeroPage        )),
+    (0xC7, OpcodeInfo::new(2, 5, Mnemonic::Smb4, AddressingMode::ZeroPage        )),
+    (0x4F, OpcodeInfo::new(3, 5, Mnemonic::Bbr4, AddressingMode::ZeroPageRelative)),
+    (0xCF, OpcodeInfo::new(3, 5, Mnemonic::Bbs4, AddressingMode::ZeroPageRelative)),
+    (0x57, OpcodeInfo::new(2, 5, Mnemonic::Rmb5, AddressingMode::ZeroPage        )),
+    (0xD7, OpcodeInfo::new(2, 5, Mnemonic::Smb5, AddressingMode::ZeroPage        )),
+    (0x5F, OpcodeInfo::new(3, 5, Mnemonic::Bbr5, AddressingMode::ZeroPageRelative)),
+    (0xDF, OpcodeInfo::new(3, 5, Mnemonic::Bbs5, AddressingMode::ZeroPageRelative)),
+    (0x67, OpcodeInfo::new(2, 5, Mnemonic::Rmb6, AddressingMode::ZeroPage        )),
+    (0xE7, OpcodeInfo::new(2, 5, Mnemonic::Smb6, AddressingMode::ZeroPage        )),
+    (0x6F, OpcodeInfo::new(3, 5, Mnemonic::Bbr6, AddressingMode::ZeroPageRelative)),
+    (0xEF, OpcodeInfo::new(3, 5, Mnemonic::Bbs6, AddressingMode::ZeroPageRelative)),
+    (0x77, OpcodeInfo::new(2, 5, Mnemonic::Rmb7, AddressingMode::ZeroPage        )),
+    (0xF7, OpcodeInfo::new(2, 5, Mnemonic::Smb7, AddressingMode::ZeroPage        )),
+    (0x7F, OpcodeInfo::new(3, 5, Mnemonic::Bbr7, AddressingMode::ZeroPageRelative)),
+    (0xFF, OpcodeInfo::new(3, 5, Mnemonic::Bbs7, AddressingMode::ZeroPageRelative)),
+
+    (0x03, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x13, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x23, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x33, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x43, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x53, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x63, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x73, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x83, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x93, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xA3, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xB3, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xC3, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xD3, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xE3, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xF3, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x0B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x1B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x2B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x3B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x4B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x5B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x6B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x7B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x8B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x9B, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xAB, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xBB, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xEB, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0xFB, OpcodeInfo::new(1, 1, Mnemonic::Nop, AddressingMode::Implied  )),
+    (0x02, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x22, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x42, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x62, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x82, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0xC2, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0xE2, OpcodeInfo::new(2, 2, Mnemonic::Nop, AddressingMode::Immediate)),
+    (0x44, OpcodeInfo::new(2, 3, Mnemonic::Nop, AddressingMode::ZeroPage )),
+    (0x54, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0xD4, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0xF4, OpcodeInfo::new(2, 4, Mnemonic::Nop, AddressingMode::ZeroPageX)),
+    (0x5C, OpcodeInfo::new(3, 8, Mnemonic::Nop, AddressingMode::Absolute )),
+    (0xDC, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::Absolute )),
+    (0xFC, OpcodeInfo::new(3, 4, Mnemonic::Nop, AddressingMode::Absolute )),
+]);
+
+/// Official and unofficial opcodes NMOS 6502 decodes.
+pub static NMOS_DECODE_TABLE: OpcodeTable = merge_tables(&OPCODE_TABLE, &UNOFFICIAL_OPCODE_TABLE);
+
+/// Opcodes 65C02 decodes.
+pub static CMOS_DECODE_TABLE: OpcodeTable = merge_tables(&OPCODE_TABLE, &CMOS_OPCODE_TABLE);
 
 #[cfg(test)]
 mod test {
@@ -264,15 +585,43 @@ mod test {
 
     #[test]
     fn test_opcode_table_with_supported_opcode() {
-        let brk = OPCODE_TABLE.get(&0x00);
-        let adc = OPCODE_TABLE.get(&0x69);
+        let brk = OPCODE_TABLE[0x00];
+        let adc = OPCODE_TABLE[0x69];
 
-        assert_eq!(brk, Some(&OpcodeInfo::new(1, 7, Mnemonic::Brk, AddressingMode::Implied)));
-        assert_eq!(adc, Some(&OpcodeInfo::new(2, 2, Mnemonic::Adc, AddressingMode::Immediate)));
+        assert_eq!(brk, Some(OpcodeInfo::new(1, 7, Mnemonic::Brk, AddressingMode::Implied)));
+        assert_eq!(adc, Some(OpcodeInfo::new(2, 2, Mnemonic::Adc, AddressingMode::Immediate)));
     }
 
     #[test]
     fn test_opcode_table_with_illegal_opcode() {
-        assert_eq!(None, OPCODE_TABLE.get(&0x02));
+        assert_eq!(None, OPCODE_TABLE[0x02]);
+    }
+
+    #[test]
+    fn test_unofficial_opcode_table_covers_rest() {
+        for opcode in 0..=0xFF {
+            let official   = OPCODE_TABLE[opcode].is_some();
+            let unofficial = UNOFFICIAL_OPCODE_TABLE[opcode].is_some();
+            assert!(official ^ unofficial, "0x{:02X}", opcode);
+        }
+    }
+
+    #[test]
+    fn test_cmos_opcode_table_covers_rest() {
+        for opcode in 0..=0xFF {
+            let official = OPCODE_TABLE[opcode].is_some();
+            let cmos     = CMOS_OPCODE_TABLE[opcode].is_some();
+            assert!(official || cmos, "0x{:02X}", opcode);
+        }
+    }
+
+    #[test]
+    fn test_decode_tables() {
+        assert_eq!(NMOS_DECODE_TABLE[0x69], OPCODE_TABLE[0x69]);
+        assert_eq!(NMOS_DECODE_TABLE[0xA7], UNOFFICIAL_OPCODE_TABLE[0xA7]);
+        assert_eq!(CMOS_DECODE_TABLE[0x6C], CMOS_OPCODE_TABLE[0x6C]);
+        assert_eq!(CMOS_DECODE_TABLE[0xEA], OPCODE_TABLE[0xEA]);
+        assert!(NMOS_DECODE_TABLE.iter().all(Option::is_some));
+        assert!(CMOS_DECODE_TABLE.iter().all(Option::is_some));
     }
 }
@@ -0,0 +1,275 @@
+//! Per-instruction conformance tests against SingleStepTests (Tom Harte's ProcessorTests).
+//!
+//! The vectors are not bundled. Get them from https://github.com/SingleStepTests/ProcessorTests
+//! and run with the directory which contains `00.json` to `ff.json`:
+//!
+//! ```sh
+//! SINGLE_STEP_TESTS_DIR=ProcessorTests/nes6502/v1 cargo test --release processor_tests -- --ignored
+//! ```
+//!
+//! The cpu behave as Ricoh 2A03 by default. Set `SINGLE_STEP_TESTS_VARIANT=6502` to
+//! run `6502/v1`, which expect decimal mode, or `SINGLE_STEP_TESTS_VARIANT=65c02`
+//! to run `wdc65c02/v1`.
+//!
+//! By default, registers, memory and the number of cycles are compared.
+//! Set `SINGLE_STEP_TESTS_BUS=1` to compare every bus access too, and
+//! `SINGLE_STEP_TESTS_OPCODES=a9,b1` to run only specified opcodes.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use serde_json::Value;
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Write,
+}
+
+// A bus access recorded by RecordingMemory. `cycle` is the index of the access in an instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BusCycle {
+    cycle: usize,
+    addr: Address,
+    value: Byte,
+    access: Access,
+}
+
+// Flat 64KB memory which record every read and write.
+// The content and the record are shared with the runner.
+#[derive(Clone)]
+struct RecordingMemory {
+    ram: Rc<RefCell<Vec<Byte>>>,
+    log: Rc<RefCell<Vec<BusCycle>>>,
+}
+
+impl RecordingMemory {
+    fn new() -> Self {
+        Self {
+            ram: Rc::new(RefCell::new(vec![0; 0x10000])),
+            log: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    fn record(&self, addr: Address, value: Byte, access: Access) {
+        let mut log = self.log.borrow_mut();
+        let cycle   = log.len();
+        log.push(BusCycle { cycle, addr, value, access });
+    }
+}
+
+impl Memory for RecordingMemory {
+    fn read_byte(&self, addr: Address) -> Byte {
+        let value = self.ram.borrow()[addr as usize];
+        self.record(addr, value, Access::Read);
+        value
+    }
+
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        self.ram.borrow_mut()[addr as usize] = value;
+        self.record(addr, value, Access::Write);
+    }
+}
+
+// Result of tests for an opcode
+#[derive(Default)]
+struct Report {
+    total: usize,
+    failed: usize,
+    first_failure: Option<String>,
+}
+
+struct Runner {
+    cpu: Cpu<RecordingMemory>,
+    mem: RecordingMemory,
+    compare_bus: bool,
+}
+
+impl Runner {
+    fn new(variant: CpuVariant, compare_bus: bool) -> Self {
+        let mem = RecordingMemory::new();
+        let cpu = Cpu::with_variant(mem.clone(), variant);
+        Self { cpu, mem, compare_bus }
+    }
+
+    // Run a test vector and return description of mismatches
+    fn run(&mut self, test: &Value) -> Vec<String> {
+        let initial = &test["initial"];
+        let expect  = &test["final"];
+
+        self.load(initial);
+        self.mem.log.borrow_mut().clear();
+
+        let mut mismatches = Vec::new();
+        let cycles = match self.cpu.step() {
+            Ok(info) => info.cycles,
+            Err(err) => {
+                mismatches.push(err.to_string());
+                0
+            }
+        };
+
+        let regs = [
+            ("pc", self.cpu.regs.pc as u64),
+            ("s",  self.cpu.regs.s  as u64),
+            ("a",  self.cpu.regs.a  as u64),
+            ("x",  self.cpu.regs.x  as u64),
+            ("y",  self.cpu.regs.y  as u64),
+            ("p",  self.cpu.regs.p.bits() as u64),
+        ];
+        for (name, actual) in regs {
+            let expected = expect[name].as_u64().unwrap();
+            if actual != expected {
+                mismatches.push(format!("{}: expected 0x{:X}, got 0x{:X}", name, expected, actual));
+            }
+        }
+
+        for (addr, expected) in ram_entries(expect) {
+            let actual = self.mem.ram.borrow()[addr as usize];
+            if actual != expected {
+                mismatches.push(format!("ram[0x{:04X}]: expected 0x{:02X}, got 0x{:02X}", addr, expected, actual));
+            }
+        }
+
+        let expected_cycles = expect_cycles(test);
+        if cycles != expected_cycles.len() as u64 {
+            mismatches.push(format!("cycles: expected {}, got {}", expected_cycles.len(), cycles));
+        }
+
+        if self.compare_bus {
+            let log = self.mem.log.borrow();
+            for (cycle, expected) in expected_cycles.iter().enumerate() {
+                match log.get(cycle) {
+                    Some(actual) if actual == expected => (),
+                    actual => mismatches.push(format!("bus cycle {}: expected {:?}, got {:?}", cycle, expected, actual)),
+                }
+            }
+            if log.len() > expected_cycles.len() {
+                mismatches.push(format!("bus: {} extra accesses", log.len() - expected_cycles.len()));
+            }
+        }
+
+        // Clear memory the test touched, so next test start with zeroed memory.
+        let mut ram = self.mem.ram.borrow_mut();
+        for (addr, _) in ram_entries(initial).into_iter().chain(ram_entries(expect)) {
+            ram[addr as usize] = 0;
+        }
+
+        mismatches
+    }
+
+    fn load(&mut self, state: &Value) {
+        let reg = |name: &str| state[name].as_u64().unwrap();
+        self.cpu.regs.pc = reg("pc") as Address;
+        self.cpu.regs.s  = reg("s")  as Byte;
+        self.cpu.regs.a  = reg("a")  as Byte;
+        self.cpu.regs.x  = reg("x")  as Byte;
+        self.cpu.regs.y  = reg("y")  as Byte;
+        self.cpu.regs.p  = Status::from_bits_truncate(reg("p") as Byte);
+        self.cpu.cycles  = 0;
+
+        let mut ram = self.mem.ram.borrow_mut();
+        for (addr, value) in ram_entries(state) {
+            ram[addr as usize] = value;
+        }
+    }
+}
+
+fn ram_entries(state: &Value) -> Vec<(Address, Byte)> {
+    state["ram"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| (entry[0].as_u64().unwrap() as Address, entry[1].as_u64().unwrap() as Byte))
+        .collect()
+}
+
+fn expect_cycles(test: &Value) -> Vec<BusCycle> {
+    test["cycles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .enumerate()
+        .map(|(cycle, entry)| BusCycle {
+            cycle,
+            addr: entry[0].as_u64().unwrap() as Address,
+            value: entry[1].as_u64().unwrap() as Byte,
+            access: if entry[2] == "write" { Access::Write } else { Access::Read },
+        })
+        .collect()
+}
+
+#[test]
+#[ignore]
+fn test_processor_tests() {
+    let dir = env::var("SINGLE_STEP_TESTS_DIR").expect("SINGLE_STEP_TESTS_DIR is not set");
+    let variant = match env::var("SINGLE_STEP_TESTS_VARIANT").as_deref() {
+        Ok("6502")  => CpuVariant::Nmos6502,
+        Ok("65c02") => CpuVariant::Wdc65C02,
+        _           => CpuVariant::Ricoh2A03,
+    };
+    let compare_bus = env::var("SINGLE_STEP_TESTS_BUS").is_ok_and(|value| value == "1");
+    let opcodes: Vec<Opcode> = match env::var("SINGLE_STEP_TESTS_OPCODES") {
+        Ok(list) => list.split(',').map(|op| Opcode::from_str_radix(op.trim(), 16).unwrap()).collect(),
+        Err(_)   => (0..=0xFF).collect(),
+    };
+
+    let mut runner  = Runner::new(variant, compare_bus);
+    let mut reports = BTreeMap::new();
+    for opcode in opcodes {
+        let path = Path::new(&dir).join(format!("{:02x}.json", opcode));
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let tests: Value = serde_json::from_str(&text).unwrap();
+
+        let report: &mut Report = reports.entry(opcode).or_default();
+        for test in tests.as_array().unwrap() {
+            let mismatches = runner.run(test);
+            report.total += 1;
+            if !mismatches.is_empty() {
+                report.failed += 1;
+                report.first_failure.get_or_insert_with(|| {
+                    format!("{}: {}", test["name"].as_str().unwrap_or(""), mismatches.join(", "))
+                });
+            }
+        }
+    }
+
+    let failures: Vec<String> = reports.iter()
+        .filter(|(_, report)| report.failed != 0)
+        .map(|(opcode, report)| format!("0x{:02X}: {}/{} failed. First failure is {}",
+                                        opcode, report.failed, report.total, report.first_failure.as_ref().unwrap()))
+        .collect();
+    assert!(!reports.is_empty(), "No test vector is found in {}", dir);
+    assert_eq!(failures.len(), 0, "{} of {} opcodes failed\n{}", failures.len(), reports.len(), failures.join("\n"));
+}
+
+#[test]
+fn test_recording_memory() {
+    let mut mem = RecordingMemory::new();
+    mem.write_byte(0x0010, 0x42);
+    mem.read_byte(0x0010);
+
+    assert_eq!(*mem.log.borrow(), vec![
+        BusCycle { cycle: 0, addr: 0x0010, value: 0x42, access: Access::Write },
+        BusCycle { cycle: 1, addr: 0x0010, value: 0x42, access: Access::Read  },
+    ]);
+}
+
+#[test]
+fn test_runner_with_vector() {
+    let test: Value = serde_json::from_str(r#"{
+        "name": "a9 42 00",
+        "initial": { "pc": 512, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36, "ram": [[512, 169], [513, 66]] },
+        "final":   { "pc": 514, "s": 253, "a": 66, "x": 0, "y": 0, "p": 36, "ram": [[512, 169], [513, 66]] },
+        "cycles":  [[512, 169, "read"], [513, 66, "read"]]
+    }"#).unwrap();
+
+    let mut runner = Runner::new(CpuVariant::Ricoh2A03, true);
+    assert_eq!(runner.run(&test), Vec::<String>::new());
+}
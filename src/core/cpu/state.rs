@@ -0,0 +1,138 @@
+//! A module that provide snapshot of the cpu state for debuggers and savestates
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use super::Cpu;
+use super::memory::Memory;
+use super::register::Status;
+use crate::core::types::*;
+
+/// Registers, cycle count and interrupt state of the cpu.
+///
+/// Memory, variant and tracer are not included, so restore this to a cpu
+/// that is created with same memory and variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CpuState {
+    pub a: Byte,
+    pub x: Byte,
+    pub y: Byte,
+    pub pc: Address,
+    pub s: Byte,
+    pub p: Byte,
+    /// The number of cycles since power on
+    pub cycles: u64,
+    /// Whether nmi line is asserted. This is needed to detect next edge.
+    pub nmi_line: bool,
+    /// Whether nmi is requested and not serviced yet
+    pub nmi_pending: bool,
+    /// Whether irq line is asserted
+    pub irq_line: bool,
+    /// Whether the cpu is waiting interrupt by Wai
+    pub waiting: bool,
+}
+
+impl<M: Memory> Cpu<M> {
+    /// Return a snapshot of the cpu state.
+    pub fn state(&self) -> CpuState {
+        CpuState {
+            a: self.regs.a,
+            x: self.regs.x,
+            y: self.regs.y,
+            pc: self.regs.pc,
+            s: self.regs.s,
+            p: self.regs.p.bits(),
+            cycles: self.cycles,
+            nmi_line: self.nmi_line,
+            nmi_pending: self.nmi_pending,
+            irq_line: self.irq_line,
+            waiting: self.waiting,
+        }
+    }
+
+    /// Restore the cpu state from a snapshot.
+    pub fn set_state(&mut self, state: &CpuState) {
+        self.regs.a      = state.a;
+        self.regs.x      = state.x;
+        self.regs.y      = state.y;
+        self.regs.pc     = state.pc;
+        self.regs.s      = state.s;
+        self.regs.p      = Status::from_bits_truncate(state.p);
+        self.cycles      = state.cycles;
+        self.nmi_line    = state.nmi_line;
+        self.nmi_pending = state.nmi_pending;
+        self.irq_line    = state.irq_line;
+        self.waiting     = state.waiting;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Ram {
+        vec: Vec<u8>,
+    }
+
+    impl Memory for Ram {
+        fn read_byte(&self, addr: Address) -> Byte {
+            self.vec[addr as usize]
+        }
+
+        fn write_byte(&mut self, addr: Address, value: Byte) {
+            self.vec[addr as usize] = value;
+        }
+    }
+
+    fn new_cpu() -> Cpu<Ram> {
+        let mut vec = vec![0xEA; 0x10000];
+        vec[0xFFFC] = 0x00;
+        vec[0xFFFD] = 0x80;
+        let mut cpu = Cpu::new(Ram { vec });
+        cpu.power_on();
+        cpu
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut cpu = new_cpu();
+        cpu.set_a(0x12);
+        cpu.set_x(0x34);
+        cpu.set_nmi_line(true);
+        cpu.step().unwrap();
+        let state = cpu.state();
+
+        let mut other = new_cpu();
+        other.set_state(&state);
+
+        assert_eq!(other.state(), state);
+        assert_eq!(other.a(), 0x12);
+        assert_eq!(other.x(), 0x34);
+        assert_eq!(other.pc(), state.pc);
+        assert_eq!(other.cycles(), state.cycles);
+        assert!(state.nmi_line);
+    }
+
+    #[test]
+    fn test_restored_state_keeps_pending_nmi() {
+        let mut cpu = new_cpu();
+        cpu.set_state(&CpuState { pc: 0x8000, s: 0xFD, nmi_pending: true, ..cpu.state() });
+        let info = cpu.step().unwrap();
+
+        assert_eq!(info.interrupt, Some(crate::core::cpu::Interrupt::Nmi));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_state_serialize() {
+        let mut cpu = new_cpu();
+        cpu.set_p(0x24);
+        cpu.set_s(0xF0);
+        let json  = serde_json::to_string(&cpu.state()).unwrap();
+        let state = serde_json::from_str::<CpuState>(&json).unwrap();
+
+        assert_eq!(state, cpu.state());
+        assert!(json.contains("\"s\":240"));
+        assert!(json.contains("\"p\":36"));
+    }
+}
@@ -0,0 +1,183 @@
+//! A module that provide tracer to log executed instructions in nestest.log format
+
+use std::io::Write;
+use super::Cpu;
+use super::memory::Memory;
+use super::opcode::*;
+use crate::core::types::*;
+
+/// A trait that receive a line per instruction from the cpu.
+///
+/// Any closure that take `&str` can be used as tracer.
+pub trait Tracer {
+    /// Called with formatted line before each instruction is executed
+    fn trace(&mut self, line: &str);
+}
+
+impl<F: FnMut(&str)> Tracer for F {
+    fn trace(&mut self, line: &str) {
+        self(line)
+    }
+}
+
+/// A tracer that write each line to `io::Write`
+pub struct WriteTracer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriteTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write> Tracer for WriteTracer<W> {
+    // Tracing must not stop the emulation, so io error is ignored.
+    fn trace(&mut self, line: &str) {
+        let _ = writeln!(self.writer, "{}", line);
+    }
+}
+
+// Memory that read through `peek_byte`, so addresses are resolved without side effects.
+struct Peek<'a, M: Memory>(&'a M);
+
+impl<M: Memory> Memory for Peek<'_, M> {
+    fn read_byte(&self, addr: Address) -> Byte {
+        self.0.peek_byte(addr)
+    }
+
+    // Tracing never write memory
+    fn write_byte(&mut self, _addr: Address, _value: Byte) {}
+}
+
+impl<M: Memory> Cpu<M> {
+    /// Format the instruction at program counter and registers as nestest.log does.
+    ///
+    /// PPU position is derived from cycles, as PPU runs 3 dots per cpu cycle.
+    /// Memory is read by `peek_byte`, so this has no side effects on devices.
+    pub fn trace_line(&self) -> String {
+        let pc     = self.regs.pc;
+        let opcode = self.mem.peek_byte(pc);
+        let (bytes, disasm) = match self.lookup_opcode(opcode) {
+            Some(info) => {
+                let bytes: Vec<String> = (0..info.byte as Address)
+                    .map(|i| format!("{:02X}", self.mem.peek_byte(pc.wrapping_add(i))))
+                    .collect();
+                let prefix = if OPCODE_TABLE[opcode as usize].is_some() { ' ' } else { '*' };
+                (bytes.join(" "), format!("{}{}", prefix, self.trace_operand(info)))
+            }
+            None => (format!("{:02X}", opcode), " ???".to_string()),
+        };
+
+        let dots = self.cycles * 3;
+        format!(
+            "{:04X}  {:<8} {:<33}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+            pc, bytes, disasm,
+            self.regs.a, self.regs.x, self.regs.y, self.regs.p.bits(), self.regs.s,
+            dots / 341 % 262, dots % 341, self.cycles,
+        )
+    }
+
+    fn trace_operand(&self, info: &OpcodeInfo) -> String {
+        // nestest.log call Isc as Isb
+        let name = match info.name {
+            Mnemonic::Isc => "ISB".to_string(),
+            name          => format!("{:?}", name).to_uppercase(),
+        };
+
+        let mem     = Peek(&self.mem);
+        let operand = self.regs.pc.wrapping_add(1);
+        let lsb     = mem.read_byte(operand);
+        let word    = mem.read_word(operand);
+        let addr    = self.effective_address(&mem, &info.mode, operand);
+        let value   = || mem.read_byte(addr);
+
+        match info.mode {
+            AddressingMode::Implied     => name,
+            AddressingMode::Accumulator => format!("{} A", name),
+            AddressingMode::Immediate   => format!("{} #${:02X}", name, lsb),
+            AddressingMode::ZeroPage    => format!("{} ${:02X} = {:02X}", name, lsb, value()),
+            AddressingMode::ZeroPageX   => format!("{} ${:02X},X @ {:02X} = {:02X}", name, lsb, addr, value()),
+            AddressingMode::ZeroPageY   => format!("{} ${:02X},Y @ {:02X} = {:02X}", name, lsb, addr, value()),
+            AddressingMode::Absolute if matches!(info.name, Mnemonic::Jmp | Mnemonic::Jsr) => {
+                format!("{} ${:04X}", name, word)
+            }
+            AddressingMode::Absolute    => format!("{} ${:04X} = {:02X}", name, word, value()),
+            AddressingMode::AbsoluteX   => format!("{} ${:04X},X @ {:04X} = {:02X}", name, word, addr, value()),
+            AddressingMode::AbsoluteY   => format!("{} ${:04X},Y @ {:04X} = {:02X}", name, word, addr, value()),
+            AddressingMode::Indirect    => format!("{} (${:04X}) = {:04X}", name, word, addr),
+            AddressingMode::IndirectX   => {
+                let pointer = lsb.wrapping_add(self.regs.x);
+                format!("{} (${:02X},X) @ {:02X} = {:04X} = {:02X}", name, lsb, pointer, addr, value())
+            }
+            AddressingMode::IndirectY   => {
+                let base = addr.wrapping_sub(self.regs.y as Address);
+                format!("{} (${:02X}),Y = {:04X} @ {:04X} = {:02X}", name, lsb, base, addr, value())
+            }
+            AddressingMode::Relative    => format!("{} ${:04X}", name, addr),
+            AddressingMode::ZeroPageIndirect  => {
+                format!("{} (${:02X}) = {:04X} = {:02X}", name, lsb, addr, value())
+            }
+            AddressingMode::AbsoluteIndirectX => format!("{} (${:04X},X) = {:04X}", name, word, addr),
+            AddressingMode::ZeroPageRelative  => {
+                let target = self.fetch_relative(&mem, operand.wrapping_add(1));
+                format!("{} ${:02X},${:04X} = {:02X}", name, lsb, target, value())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    // Memory that count reads, as reading devices may have side effects
+    struct CountingMemory {
+        vec: Vec<u8>,
+        reads: Cell<usize>,
+    }
+
+    impl Memory for CountingMemory {
+        fn read_byte(&self, addr: Address) -> Byte {
+            self.reads.set(self.reads.get() + 1);
+            self.vec[addr as usize]
+        }
+
+        fn write_byte(&mut self, addr: Address, value: Byte) {
+            self.vec[addr as usize] = value;
+        }
+
+        fn peek_byte(&self, addr: Address) -> Byte {
+            self.vec[addr as usize]
+        }
+    }
+
+    #[test]
+    fn test_trace_line_peeks_memory() {
+        let mut vec = vec![0x00; 0x10000];
+        // LDA ($10),Y
+        vec[0x8000] = 0xB1;
+        vec[0x8001] = 0x10;
+        vec[0x0010] = 0x00;
+        vec[0x0011] = 0x20;
+        vec[0x2002] = 0x80;
+        vec[0xFFFD] = 0x80;
+        let mut cpu = Cpu::new(CountingMemory { vec, reads: Cell::new(0) });
+        cpu.power_on();
+        cpu.set_y(0x02);
+        cpu.mem.reads.set(0);
+
+        assert!(cpu.trace_line().starts_with("8000  B1 10     LDA ($10),Y = 2000 @ 2002 = 80"));
+        assert_eq!(cpu.mem.reads.get(), 0);
+    }
+
+    #[test]
+    fn test_write_tracer() {
+        let mut tracer = WriteTracer::new(Vec::new());
+        tracer.trace("C000  4C F5 C5  JMP $C5F5");
+        tracer.trace("C5F5  A2 00     LDX #$00");
+
+        assert_eq!(tracer.writer, b"C000  4C F5 C5  JMP $C5F5\nC5F5  A2 00     LDX #$00\n");
+    }
+}
@@ -0,0 +1,61 @@
+//! A module that provide variants of 6502 the cpu can behave as
+
+use super::opcode::*;
+
+/// The enum that represent which 6502 the cpu behave as
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CpuVariant {
+    /// Ricoh 2A03 used in NES. This executes unofficial opcodes as real hardware,
+    /// but decimal flag doesn't affect adc and sbc.
+    #[default]
+    Ricoh2A03,
+    /// Same as Ricoh2A03, but only official opcodes are accepted.
+    ///
+    /// Unofficial opcodes cause `CpuError::IllegalOpcode`, so this is useful to find
+    /// programs that rely on them.
+    OfficialOnly,
+    /// Original NMOS 6502. Adc and sbc do decimal arithmetic when decimal flag is set.
+    Nmos6502,
+    /// WDC 65C02. This has additional instructions and addressing mode, and fixes
+    /// some bugs of NMOS 6502. Undefined opcodes are executed as no operation.
+    Wdc65C02,
+}
+
+impl CpuVariant {
+    /// Return true if the variant executes unofficial opcodes
+    pub fn has_unofficial_opcodes(&self) -> bool {
+        match self {
+            CpuVariant::Ricoh2A03    => true,
+            CpuVariant::OfficialOnly => false,
+            CpuVariant::Nmos6502     => true,
+            CpuVariant::Wdc65C02     => false,
+        }
+    }
+
+    /// Return true if the variant does decimal arithmetic when decimal flag is set
+    pub fn has_decimal_mode(&self) -> bool {
+        match self {
+            CpuVariant::Ricoh2A03    => false,
+            CpuVariant::OfficialOnly => false,
+            CpuVariant::Nmos6502     => true,
+            CpuVariant::Wdc65C02     => true,
+        }
+    }
+
+    /// Return true if the variant is cmos 65C02
+    pub fn is_cmos(&self) -> bool {
+        *self == CpuVariant::Wdc65C02
+    }
+
+    // Unofficial opcodes are decoded only if the variant has them.
+    pub(crate) fn lookup_opcode(&self, opcode: Opcode) -> Option<&'static OpcodeInfo> {
+        let table = if self.is_cmos() {
+            &CMOS_DECODE_TABLE
+        } else if self.has_unofficial_opcodes() {
+            &NMOS_DECODE_TABLE
+        } else {
+            &OPCODE_TABLE
+        };
+        table[opcode as usize].as_ref()
+    }
+}
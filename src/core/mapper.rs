@@ -0,0 +1,256 @@
+//! A module that provide mappers, which are circuits on cartridges to switch banks
+//!
+//! Cpu and ppu access a cartridge through `Mapper`. Use `new_mapper` to build
+//! the mapper a cartridge requires.
+
+mod axrom;
+mod cnrom;
+mod color_dreams;
+mod gxrom;
+mod mmc1;
+mod mmc3;
+mod nrom;
+mod uxrom;
+
+pub use self::axrom::Axrom;
+pub use self::cnrom::Cnrom;
+pub use self::color_dreams::ColorDreams;
+pub use self::gxrom::Gxrom;
+pub use self::mmc1::Mmc1;
+pub use self::mmc3::{A12Watcher, Mmc3};
+pub use self::nrom::Nrom;
+pub use self::uxrom::Uxrom;
+
+use std::fmt;
+use super::cartridge::{Cartoridge, Mirroring};
+use super::types::*;
+
+/// A trait that represent a cartridge with its mapper.
+///
+/// Cpu accesses 0x4020-0xFFFF and ppu accesses pattern tables in 0x0000-0x1FFF through this.
+pub trait Mapper {
+    /// Return a value at `addr` of cpu address space, or None if nothing drives the bus.
+    fn cpu_read(&self, addr: Address) -> Option<Byte>;
+
+    /// Write a value to `addr` of cpu address space, which may switch banks.
+    fn cpu_write(&mut self, addr: Address, value: Byte);
+
+    /// Return a value at `addr` of ppu address space.
+    fn ppu_read(&self, addr: Address) -> Byte;
+
+    /// Write a value to `addr` of ppu address space. This has effect only on CHR RAM.
+    fn ppu_write(&mut self, addr: Address, value: Byte);
+
+    /// Return current nametable mirroring.
+    fn mirroring(&self) -> Mirroring;
+
+    /// Return true if the mapper asserts irq line.
+    fn irq(&self) -> bool {
+        false
+    }
+
+    /// Called after cpu consumed `cycles` cycles.
+    ///
+    /// Mappers that ignore writes on consecutive cycles, like MMC1, count on this.
+    /// Without ticks every write is treated as consecutive to the previous one.
+    fn tick(&mut self, _cycles: u64) {}
+
+    /// Called by ppu when A12 of ppu address rises. See `A12Watcher`.
+    fn a12_rise(&mut self) {}
+}
+
+/// The enum that represent error while building a mapper
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapperError {
+    /// The iNES mapper number is not implemented.
+    Unsupported { mapper: u16 },
+    /// The cartridge has no PRG ROM, which every mapper requires.
+    EmptyPrgRom,
+}
+
+impl fmt::Display for MapperError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapperError::Unsupported { mapper } => write!(f, "Unsupported mapper {}", mapper),
+            MapperError::EmptyPrgRom => write!(f, "The cartridge has no PRG ROM"),
+        }
+    }
+}
+
+impl std::error::Error for MapperError {}
+
+/// Build the mapper from iNES mapper number of `cartridge`, which must have PRG ROM.
+///
+/// Bus conflicts are enabled only if NES 2.0 submapper 2 requests them. Build the
+/// mapper directly to enable them for other cartridges.
+pub fn new_mapper(cartridge: Cartoridge) -> Result<Box<dyn Mapper>, MapperError> {
+    if cartridge.prg_rom.is_empty() {
+        return Err(MapperError::EmptyPrgRom);
+    }
+
+    let bus_conflicts = cartridge.submapper == 2;
+    match cartridge.mapper {
+        0  => Ok(Box::new(Nrom::new(cartridge))),
+        1  => Ok(Box::new(Mmc1::new(cartridge))),
+        2  => {
+            let mut mapper = Uxrom::new(cartridge);
+            mapper.set_bus_conflicts(bus_conflicts);
+            Ok(Box::new(mapper))
+        }
+        3  => {
+            let mut mapper = Cnrom::new(cartridge);
+            mapper.set_bus_conflicts(bus_conflicts);
+            Ok(Box::new(mapper))
+        }
+        4  => Ok(Box::new(Mmc3::new(cartridge))),
+        7  => {
+            let mut mapper = Axrom::new(cartridge);
+            mapper.set_bus_conflicts(bus_conflicts);
+            Ok(Box::new(mapper))
+        }
+        11 => {
+            let mut mapper = ColorDreams::new(cartridge);
+            mapper.set_bus_conflicts(bus_conflicts);
+            Ok(Box::new(mapper))
+        }
+        66 => {
+            let mut mapper = Gxrom::new(cartridge);
+            mapper.set_bus_conflicts(bus_conflicts);
+            Ok(Box::new(mapper))
+        }
+        mapper => Err(MapperError::Unsupported { mapper }),
+    }
+}
+
+// Return index in memory of `len` bytes, where `addr` is in `bank` of `size` bytes.
+// Banks beyond the memory are mirrored, and empty memory returns 0.
+fn bank_index(len: usize, bank: usize, size: usize, addr: Address) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let banks = (len / size).max(1);
+    ((bank % banks) * size + addr as usize % size) % len
+}
+
+// A register of discrete logic boards, which latches a value written to 0x8000-0xFFFF.
+// On boards without a buffer on data bus, ROM also drives the bus on the write, so
+// the latched value is ANDed with the ROM byte at the address. This is bus conflicts.
+#[derive(Debug, Default)]
+struct Latch {
+    value: Byte,
+    bus_conflicts: bool,
+}
+
+impl Latch {
+    // Latch `value` written where `rom` is read.
+    fn write(&mut self, rom: Byte, value: Byte) {
+        self.value = if self.bus_conflicts { value & rom } else { value };
+    }
+}
+
+// Pattern tables of a cartridge. 8KB CHR RAM is used when the cartridge has no CHR ROM.
+struct Chr {
+    memory: Vec<Byte>,
+    is_ram: bool,
+}
+
+impl Chr {
+    fn new(chr_rom: Vec<Byte>) -> Self {
+        let is_ram = chr_rom.is_empty();
+        Self { memory: if is_ram { vec![0; 0x2000] } else { chr_rom }, is_ram }
+    }
+
+    // Return a value at `addr` in `bank` of `size` bytes.
+    fn read(&self, bank: usize, size: usize, addr: Address) -> Byte {
+        self.memory[bank_index(self.memory.len(), bank, size, addr)]
+    }
+
+    // Write a value at `addr` in `bank` of `size` bytes. This has effect only on CHR RAM.
+    fn write(&mut self, bank: usize, size: usize, addr: Address, value: Byte) {
+        if self.is_ram {
+            let index = bank_index(self.memory.len(), bank, size, addr);
+            self.memory[index] = value;
+        }
+    }
+}
+
+// Return ROM of `len` bytes whose bytes are the number of the bank of `size` bytes.
+#[cfg(test)]
+fn bank_numbered_rom(len: usize, size: usize) -> Vec<Byte> {
+    (0..len).map(|i| (i / size) as Byte).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_cartridge(mapper: u16) -> Cartoridge {
+        Cartoridge { prg_rom: vec![0; 0x4000], chr_rom: vec![0; 0x2000], mapper, mirroring: Mirroring::Vertical, ..Default::default() }
+    }
+
+    #[test]
+    fn test_new_mapper() {
+        let mapper = new_mapper(new_cartridge(0)).unwrap();
+
+        assert_eq!(mapper.mirroring(), Mirroring::Vertical);
+        assert!(!mapper.irq());
+    }
+
+    #[test]
+    fn test_bus_conflicts_by_submapper() {
+        // ROM bytes are 0, so any written value selects CHR bank 0 with bus conflicts.
+        for mapper in [3, 11, 66] {
+            let chr_rom    = bank_numbered_rom(0x8000, 0x2000);
+            let mut mapper = new_mapper(Cartoridge { prg_rom: vec![0; 0x8000], chr_rom, mapper, submapper: 2, ..Default::default() }).unwrap();
+            mapper.cpu_write(0x8000, 0x12);
+
+            assert_eq!(mapper.ppu_read(0x0000), 0x00);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_mapper() {
+        let error = new_mapper(new_cartridge(255)).err().unwrap();
+
+        assert_eq!(error, MapperError::Unsupported { mapper: 255 });
+        assert_eq!(error.to_string(), "Unsupported mapper 255");
+    }
+
+    #[test]
+    fn test_empty_prg_rom() {
+        for mapper in [0, 1, 2, 3, 4, 7, 11, 66] {
+            let cartridge = Cartoridge { prg_rom: Vec::new(), mapper, ..Default::default() };
+            assert_eq!(new_mapper(cartridge).err(), Some(MapperError::EmptyPrgRom));
+        }
+    }
+
+    // Mappers built directly from a cartridge without PRG ROM don't drive the bus.
+    #[test]
+    fn test_mappers_read_empty_prg_rom() {
+        let cartridge = || Cartoridge { prg_rom: Vec::new(), ..Default::default() };
+        let mappers: Vec<Box<dyn Mapper>> = vec![
+            Box::new(Nrom::new(cartridge())),
+            Box::new(Mmc1::new(cartridge())),
+            Box::new(Uxrom::new(cartridge())),
+            Box::new(Cnrom::new(cartridge())),
+            Box::new(Mmc3::new(cartridge())),
+            Box::new(Axrom::new(cartridge())),
+            Box::new(ColorDreams::new(cartridge())),
+            Box::new(Gxrom::new(cartridge())),
+        ];
+        for mut mapper in mappers {
+            mapper.cpu_write(0x8000, 0x01);
+            assert_eq!(mapper.cpu_read(0xFFFC), None);
+        }
+    }
+
+    #[test]
+    fn test_bank_index() {
+        assert_eq!(bank_index(0x8000, 1, 0x4000, 0xC123), 0x4123);
+        // Bank 3 is mirrored to bank 1
+        assert_eq!(bank_index(0x8000, 3, 0x4000, 0x8123), 0x4123);
+        // Memory smaller than a bank is mirrored in the bank
+        assert_eq!(bank_index(0x2000, 0, 0x8000, 0xA123), 0x0123);
+        assert_eq!(bank_index(0, 3, 0x2000, 0x1123), 0);
+    }
+}
@@ -0,0 +1,97 @@
+//! A module that provide AxROM (mapper 7), which switches 32KB PRG bank and single screen mirroring
+
+use super::{bank_index, Chr, Latch, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_SIZE:      usize = 0x2000;
+
+/// AxROM with switchable 32KB PRG bank, CHR RAM and single screen mirroring
+pub struct Axrom {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    // Bits 0-2 select PRG bank, and bit 4 selects nametable
+    latch: Latch,
+}
+
+impl Axrom {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            latch: Latch::default(),
+        }
+    }
+
+    /// Set whether the board has bus conflicts.
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.latch.bus_conflicts = enabled;
+    }
+}
+
+impl Mapper for Axrom {
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x8000..=0xFFFF => {
+                let bank = (self.latch.value & 0b0111) as usize;
+                self.prg_rom.get(bank_index(self.prg_rom.len(), bank, PRG_BANK_SIZE, addr)).copied()
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        if let Some(rom) = self.cpu_read(addr) {
+            self.latch.write(rom, value);
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read(0, CHR_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write(0, CHR_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.latch.value & 0b1_0000 == 0 { Mirroring::SingleScreenLower } else { Mirroring::SingleScreenUpper }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 256KB PRG ROM whose bytes are the bank number, and CHR RAM
+    fn new_axrom() -> Axrom {
+        let prg_rom = bank_numbered_rom(0x40000, PRG_BANK_SIZE);
+        Axrom::new(Cartoridge { prg_rom, chr_rom: Vec::new(), mapper: 7, mirroring: Mirroring::Horizontal, ..Default::default() })
+    }
+
+    #[test]
+    fn test_prg_bank_and_mirroring() {
+        let mut axrom = new_axrom();
+
+        assert_eq!(axrom.cpu_read(0x8000), Some(0));
+        assert_eq!(axrom.mirroring(), Mirroring::SingleScreenLower);
+
+        axrom.cpu_write(0x8000, 0b1_0110);
+        assert_eq!(axrom.cpu_read(0x8000), Some(6));
+        assert_eq!(axrom.cpu_read(0xFFFF), Some(6));
+        assert_eq!(axrom.mirroring(), Mirroring::SingleScreenUpper);
+    }
+
+    #[test]
+    fn test_bus_conflicts() {
+        let mut axrom = new_axrom();
+        axrom.cpu_write(0x8000, 3);
+        axrom.set_bus_conflicts(true);
+        axrom.cpu_write(0x8000, 0b1_0110);
+
+        assert_eq!(axrom.cpu_read(0x8000), Some(2));
+        assert_eq!(axrom.mirroring(), Mirroring::SingleScreenLower);
+    }
+}
@@ -0,0 +1,112 @@
+//! A module that provide CNROM (mapper 3), which switches 8KB CHR bank
+
+use super::{bank_index, Chr, Latch, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_ROM_SIZE:  usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// CNROM with fixed 16KB or 32KB PRG ROM and switchable 8KB CHR bank of CHR ROM or CHR RAM
+pub struct Cnrom {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    mirroring: Mirroring,
+    // CHR bank
+    latch: Latch,
+}
+
+impl Cnrom {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            mirroring: cartridge.mirroring,
+            latch: Latch::default(),
+        }
+    }
+
+    /// Set whether the board has bus conflicts.
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.latch.bus_conflicts = enabled;
+    }
+}
+
+impl Mapper for Cnrom {
+    // 16KB PRG ROM is mirrored to 0xC000-0xFFFF
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x8000..=0xFFFF => self.prg_rom.get(bank_index(self.prg_rom.len(), 0, PRG_ROM_SIZE, addr)).copied(),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        if let Some(rom) = self.cpu_read(addr) {
+            self.latch.write(rom, value);
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read(self.latch.value as usize, CHR_BANK_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write(self.latch.value as usize, CHR_BANK_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 32KB PRG ROM filled with 0xFF except 0x8000, and 32KB CHR ROM whose bytes are the bank number
+    fn new_cnrom() -> Cnrom {
+        let mut prg_rom = vec![0xFF; 0x8000];
+        prg_rom[0] = 0x01;
+        let chr_rom = bank_numbered_rom(0x8000, CHR_BANK_SIZE);
+        Cnrom::new(Cartoridge { prg_rom, chr_rom, mapper: 3, mirroring: Mirroring::Horizontal, ..Default::default() })
+    }
+
+    #[test]
+    fn test_chr_bank() {
+        let mut cnrom = new_cnrom();
+        cnrom.cpu_write(0xFFFF, 2);
+
+        assert_eq!(cnrom.ppu_read(0x0000), 2);
+        assert_eq!(cnrom.ppu_read(0x1FFF), 2);
+        assert_eq!(cnrom.cpu_read(0x8000), Some(0x01));
+        assert_eq!(cnrom.cpu_read(0xC000), Some(0xFF));
+    }
+
+    #[test]
+    fn test_chr_rom_is_read_only() {
+        let mut cnrom = new_cnrom();
+        cnrom.ppu_write(0x0000, 0x42);
+
+        assert_eq!(cnrom.ppu_read(0x0000), 0);
+    }
+
+    #[test]
+    fn test_chr_ram() {
+        let mut cnrom = Cnrom::new(Cartoridge { prg_rom: vec![0; 0x8000], mapper: 3, ..Default::default() });
+        cnrom.cpu_write(0x8000, 2);
+        cnrom.ppu_write(0x1234, 0x42);
+
+        assert_eq!(cnrom.ppu_read(0x1234), 0x42);
+    }
+
+    #[test]
+    fn test_bus_conflicts() {
+        let mut cnrom = new_cnrom();
+        cnrom.set_bus_conflicts(true);
+        cnrom.cpu_write(0x8000, 3);
+
+        assert_eq!(cnrom.ppu_read(0x0000), 1);
+    }
+}
@@ -0,0 +1,108 @@
+//! A module that provide Color Dreams (mapper 11), which switches 32KB PRG bank and 8KB CHR bank
+
+use super::{bank_index, Chr, Latch, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// Color Dreams with switchable 32KB PRG bank and 8KB CHR bank of CHR ROM or CHR RAM
+pub struct ColorDreams {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    mirroring: Mirroring,
+    // Bits 0-1 select PRG bank, and bits 4-7 select CHR bank
+    latch: Latch,
+}
+
+impl ColorDreams {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            mirroring: cartridge.mirroring,
+            latch: Latch::default(),
+        }
+    }
+
+    /// Set whether the board has bus conflicts.
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.latch.bus_conflicts = enabled;
+    }
+}
+
+impl Mapper for ColorDreams {
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x8000..=0xFFFF => {
+                let bank = (self.latch.value & 0b11) as usize;
+                self.prg_rom.get(bank_index(self.prg_rom.len(), bank, PRG_BANK_SIZE, addr)).copied()
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        if let Some(rom) = self.cpu_read(addr) {
+            self.latch.write(rom, value);
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read((self.latch.value >> 4) as usize, CHR_BANK_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write((self.latch.value >> 4) as usize, CHR_BANK_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 128KB PRG ROM and 128KB CHR ROM, whose bytes are the bank number
+    fn new_color_dreams() -> ColorDreams {
+        let prg_rom = bank_numbered_rom(0x20000, PRG_BANK_SIZE);
+        let chr_rom = bank_numbered_rom(0x20000, CHR_BANK_SIZE);
+        ColorDreams::new(Cartoridge { prg_rom, chr_rom, mapper: 11, mirroring: Mirroring::Vertical, ..Default::default() })
+    }
+
+    #[test]
+    fn test_prg_and_chr_bank() {
+        let mut color_dreams = new_color_dreams();
+        color_dreams.cpu_write(0x8000, 0xD2);
+
+        assert_eq!(color_dreams.cpu_read(0x8000), Some(2));
+        assert_eq!(color_dreams.cpu_read(0xFFFF), Some(2));
+        assert_eq!(color_dreams.ppu_read(0x0000), 13);
+        assert_eq!(color_dreams.ppu_read(0x1FFF), 13);
+    }
+
+    #[test]
+    fn test_chr_ram() {
+        let mut color_dreams = ColorDreams::new(Cartoridge { prg_rom: vec![0; 0x8000], mapper: 11, ..Default::default() });
+        color_dreams.cpu_write(0x8000, 0x30);
+        color_dreams.ppu_write(0x1234, 0x42);
+
+        assert_eq!(color_dreams.ppu_read(0x1234), 0x42);
+    }
+
+    #[test]
+    fn test_bus_conflicts() {
+        let mut color_dreams = new_color_dreams();
+        color_dreams.cpu_write(0x8000, 0x03);
+        color_dreams.set_bus_conflicts(true);
+        // ROM byte is 3 in PRG bank 3
+        color_dreams.cpu_write(0x8000, 0xF6);
+
+        assert_eq!(color_dreams.cpu_read(0x8000), Some(2));
+        assert_eq!(color_dreams.ppu_read(0x0000), 0);
+    }
+}
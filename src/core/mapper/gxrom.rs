@@ -0,0 +1,108 @@
+//! A module that provide GxROM (mapper 66), which switches 32KB PRG bank and 8KB CHR bank
+
+use super::{bank_index, Chr, Latch, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// GxROM with switchable 32KB PRG bank and 8KB CHR bank of CHR ROM or CHR RAM
+pub struct Gxrom {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    mirroring: Mirroring,
+    // Bits 4-5 select PRG bank, and bits 0-1 select CHR bank
+    latch: Latch,
+}
+
+impl Gxrom {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            mirroring: cartridge.mirroring,
+            latch: Latch::default(),
+        }
+    }
+
+    /// Set whether the board has bus conflicts.
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.latch.bus_conflicts = enabled;
+    }
+}
+
+impl Mapper for Gxrom {
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x8000..=0xFFFF => {
+                let bank = ((self.latch.value >> 4) & 0b11) as usize;
+                self.prg_rom.get(bank_index(self.prg_rom.len(), bank, PRG_BANK_SIZE, addr)).copied()
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        if let Some(rom) = self.cpu_read(addr) {
+            self.latch.write(rom, value);
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read((self.latch.value & 0b11) as usize, CHR_BANK_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write((self.latch.value & 0b11) as usize, CHR_BANK_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 128KB PRG ROM and 32KB CHR ROM, whose bytes are the bank number
+    fn new_gxrom() -> Gxrom {
+        let prg_rom = bank_numbered_rom(0x20000, PRG_BANK_SIZE);
+        let chr_rom = bank_numbered_rom(0x8000, CHR_BANK_SIZE);
+        Gxrom::new(Cartoridge { prg_rom, chr_rom, mapper: 66, mirroring: Mirroring::Vertical, ..Default::default() })
+    }
+
+    #[test]
+    fn test_prg_and_chr_bank() {
+        let mut gxrom = new_gxrom();
+        gxrom.cpu_write(0x8000, 0b10_0011);
+
+        assert_eq!(gxrom.cpu_read(0x8000), Some(2));
+        assert_eq!(gxrom.cpu_read(0xFFFF), Some(2));
+        assert_eq!(gxrom.ppu_read(0x0000), 3);
+        assert_eq!(gxrom.ppu_read(0x1FFF), 3);
+    }
+
+    #[test]
+    fn test_chr_ram() {
+        let mut gxrom = Gxrom::new(Cartoridge { prg_rom: vec![0; 0x8000], mapper: 66, ..Default::default() });
+        gxrom.cpu_write(0x8000, 0b00_0011);
+        gxrom.ppu_write(0x1234, 0x42);
+
+        assert_eq!(gxrom.ppu_read(0x1234), 0x42);
+    }
+
+    #[test]
+    fn test_bus_conflicts() {
+        let mut gxrom = new_gxrom();
+        gxrom.cpu_write(0x8000, 0b01_0000);
+        gxrom.set_bus_conflicts(true);
+        // ROM byte is 1 in PRG bank 1
+        gxrom.cpu_write(0x8000, 0b11_0011);
+
+        assert_eq!(gxrom.cpu_read(0x8000), Some(0));
+        assert_eq!(gxrom.ppu_read(0x0000), 1);
+    }
+}
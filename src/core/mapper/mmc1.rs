@@ -0,0 +1,267 @@
+//! A module that provide MMC1 (mapper 1), whose registers are written serially
+
+use super::{bank_index, Chr, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+const PRG_RAM_SIZE:  usize = 0x2000;
+
+/// MMC1 with 16KB PRG banks, 4KB CHR banks and 8KB PRG RAM
+///
+/// Registers are written through 5-bit shift register at 0x8000-0xFFFF, one bit per write.
+/// Writes on consecutive cycles are ignored, so the mapper must be ticked. Without
+/// ticks every write is treated as consecutive and only the first write takes effect.
+pub struct Mmc1 {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    prg_ram: Vec<Byte>,
+    // Bits written so far. The 1 initially at bit 4 reaches bit 0 at fifth write.
+    shift: Byte,
+    control: Byte,
+    chr_bank0: Byte,
+    chr_bank1: Byte,
+    prg_bank: Byte,
+    // Cycles since power on, and the cycle of the last write to shift register
+    cycles: u64,
+    last_write: Option<u64>,
+}
+
+impl Mmc1 {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            shift: 0b1_0000,
+            // Last bank is fixed at 0xC000 on power on
+            control: 0b0_1100,
+            chr_bank0: 0,
+            chr_bank1: 0,
+            prg_bank: 0,
+            cycles: 0,
+            last_write: None,
+        }
+    }
+
+    // Bit 4 of PRG bank register disables PRG RAM.
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0b1_0000 == 0
+    }
+
+    fn write_shift(&mut self, addr: Address, value: Byte) {
+        // Writes on consecutive cycles, like double writes of read-modify-write
+        // instructions, are ignored except the first one.
+        let is_consecutive = self.last_write.is_some_and(|cycle| self.cycles - cycle <= 1);
+        self.last_write    = Some(self.cycles);
+        if is_consecutive {
+            return;
+        }
+
+        if value & 0b1000_0000 != 0 {
+            self.shift    = 0b1_0000;
+            self.control |= 0b0_1100;
+            return;
+        }
+
+        let is_full = self.shift & 1 == 1;
+        self.shift  = (self.shift >> 1) | ((value & 1) << 4);
+        if !is_full {
+            return;
+        }
+
+        let register = self.shift;
+        self.shift   = 0b1_0000;
+        match addr {
+            0x8000..=0x9FFF => self.control   = register,
+            0xA000..=0xBFFF => self.chr_bank0 = register,
+            0xC000..=0xDFFF => self.chr_bank1 = register,
+            _               => self.prg_bank  = register,
+        }
+    }
+
+    fn prg_index(&self, addr: Address) -> usize {
+        let bank = (self.prg_bank & 0b1111) as usize;
+        let last = (self.prg_rom.len() / PRG_BANK_SIZE).saturating_sub(1);
+        let bank = match ((self.control >> 2) & 0b11, addr) {
+            // 32KB mode ignores low bit of the bank
+            (0, _) | (1, _)       => (bank & !1) | ((addr as usize - 0x8000) / PRG_BANK_SIZE),
+            (2, 0x8000..=0xBFFF)  => 0,
+            (2, _)                => bank,
+            (_, 0x8000..=0xBFFF)  => bank,
+            (_, _)                => last,
+        };
+        bank_index(self.prg_rom.len(), bank, PRG_BANK_SIZE, addr)
+    }
+
+    fn chr_bank(&self, addr: Address) -> usize {
+        match (self.control & 0b1_0000 != 0, addr) {
+            // 8KB mode ignores low bit of the bank
+            (false, _)              => (self.chr_bank0 & !1) as usize | (addr as usize / CHR_BANK_SIZE),
+            (true, 0x0000..=0x0FFF) => self.chr_bank0 as usize,
+            (true, _)               => self.chr_bank1 as usize,
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => Some(self.prg_ram[addr as usize % PRG_RAM_SIZE]),
+            0x8000..=0xFFFF => self.prg_rom.get(self.prg_index(addr)).copied(),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => self.prg_ram[addr as usize % PRG_RAM_SIZE] = value,
+            0x8000..=0xFFFF => self.write_shift(addr, value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read(self.chr_bank(addr), CHR_BANK_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write(self.chr_bank(addr), CHR_BANK_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.cycles += cycles;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 256KB PRG ROM and 128KB CHR ROM, whose bytes are the bank number
+    fn new_mmc1() -> Mmc1 {
+        let prg_rom = bank_numbered_rom(0x40000, PRG_BANK_SIZE);
+        let chr_rom = bank_numbered_rom(0x20000, CHR_BANK_SIZE);
+        Mmc1::new(Cartoridge { prg_rom, chr_rom, mapper: 1, mirroring: Mirroring::Horizontal, ..Default::default() })
+    }
+
+    // Write 5 bits from lsb as games do, with enough cycles between writes.
+    fn write_register(mmc1: &mut Mmc1, addr: Address, value: Byte) {
+        for i in 0..5 {
+            mmc1.cpu_write(addr, value >> i);
+            mmc1.tick(2);
+        }
+    }
+
+    #[test]
+    fn test_power_on_fixes_last_bank() {
+        let mmc1 = new_mmc1();
+
+        assert_eq!(mmc1.cpu_read(0x8000), Some(0));
+        assert_eq!(mmc1.cpu_read(0xFFFF), Some(15));
+    }
+
+    #[test]
+    fn test_prg_rom_smaller_than_bank() {
+        let prg_rom = (0..0x2000).map(|i| i as Byte).collect();
+        let mmc1    = Mmc1::new(Cartoridge { prg_rom, mapper: 1, ..Default::default() });
+
+        assert_eq!(mmc1.cpu_read(0xC001), Some(1));
+    }
+
+    #[test]
+    fn test_prg_bank_modes() {
+        let mut mmc1 = new_mmc1();
+        write_register(&mut mmc1, 0xE000, 5);
+
+        assert_eq!(mmc1.cpu_read(0x8000), Some(5));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(15));
+
+        // Fix first bank at 0x8000
+        write_register(&mut mmc1, 0x8000, 0b0_1000);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(0));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(5));
+
+        // 32KB mode
+        write_register(&mut mmc1, 0x8000, 0b0_0000);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(4));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(5));
+    }
+
+    #[test]
+    fn test_chr_bank_modes() {
+        let mut mmc1 = new_mmc1();
+        write_register(&mut mmc1, 0xA000, 3);
+        write_register(&mut mmc1, 0xC000, 9);
+
+        // 8KB mode
+        assert_eq!(mmc1.ppu_read(0x0000), 2);
+        assert_eq!(mmc1.ppu_read(0x1000), 3);
+
+        // 4KB mode
+        write_register(&mut mmc1, 0x8000, 0b1_1100);
+        assert_eq!(mmc1.ppu_read(0x0000), 3);
+        assert_eq!(mmc1.ppu_read(0x1FFF), 9);
+    }
+
+    #[test]
+    fn test_mirroring() {
+        let mut mmc1 = new_mmc1();
+        let modes = [Mirroring::SingleScreenLower, Mirroring::SingleScreenUpper, Mirroring::Vertical, Mirroring::Horizontal];
+        for (value, mirroring) in modes.into_iter().enumerate() {
+            write_register(&mut mmc1, 0x8000, 0b0_1100 | value as Byte);
+            assert_eq!(mmc1.mirroring(), mirroring);
+        }
+    }
+
+    #[test]
+    fn test_prg_ram() {
+        let mut mmc1 = new_mmc1();
+        mmc1.cpu_write(0x6000, 0x42);
+
+        assert_eq!(mmc1.cpu_read(0x6000), Some(0x42));
+
+        write_register(&mut mmc1, 0xE000, 0b1_0000);
+        assert_eq!(mmc1.cpu_read(0x6000), None);
+    }
+
+    #[test]
+    fn test_reset_shift_register() {
+        let mut mmc1 = new_mmc1();
+        write_register(&mut mmc1, 0x8000, 0b0_0000);
+        mmc1.cpu_write(0xE000, 1);
+        mmc1.tick(2);
+        mmc1.cpu_write(0xE000, 0x80);
+        mmc1.tick(2);
+        write_register(&mut mmc1, 0xE000, 2);
+
+        // Reset also fixes last bank
+        assert_eq!(mmc1.cpu_read(0x8000), Some(2));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(15));
+    }
+
+    #[test]
+    fn test_consecutive_write_is_ignored() {
+        let mut mmc1 = new_mmc1();
+        for _ in 0..5 {
+            // The second write is on next cycle, as INC does.
+            mmc1.cpu_write(0xE000, 1);
+            mmc1.tick(1);
+            mmc1.cpu_write(0xE000, 0);
+            mmc1.tick(2);
+        }
+
+        assert_eq!(mmc1.cpu_read(0x8000), Some(15));
+    }
+}
@@ -0,0 +1,321 @@
+//! A module that provide MMC3 (mapper 4), which has scanline counter to raise irq
+
+use super::{bank_index, Chr, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+const PRG_RAM_SIZE:  usize = 0x2000;
+
+// A12 must be low for about 3 cpu cycles before a rise is counted.
+const A12_FILTER_DOTS: u64 = 9;
+
+/// A helper for ppu to detect rises of A12, bit 12 of ppu address.
+///
+/// MMC3 counts a rise as a scanline. Rises after A12 is low only a few dots are
+/// ignored as MMC3 does, so 8 sprite fetches in a scanline are counted once.
+#[derive(Debug, Default)]
+pub struct A12Watcher {
+    is_high: bool,
+    low_since: u64,
+}
+
+impl A12Watcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update A12 with ppu address accessed at `dot`, and return true if A12 rises.
+    ///
+    /// If `dot` is before the dot A12 went low, the dot counter is regarded as restarted
+    /// and A12 as low long enough.
+    pub fn update(&mut self, addr: Address, dot: u64) -> bool {
+        let is_high = addr & 0x1000 != 0;
+        let is_long = dot < self.low_since || dot - self.low_since >= A12_FILTER_DOTS;
+        let is_rise = is_high && !self.is_high && is_long;
+        if !is_high && self.is_high {
+            self.low_since = dot;
+        }
+        self.is_high = is_high;
+        is_rise
+    }
+}
+
+/// MMC3 with 8KB PRG banks, 1KB and 2KB CHR banks, 8KB PRG RAM and scanline irq
+pub struct Mmc3 {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    prg_ram: Vec<Byte>,
+    // Bank register to be written by next bank data, and bank modes
+    bank_select: Byte,
+    // R0-R5 are CHR banks and R6-R7 are PRG banks
+    banks: [Byte; 8],
+    mirroring: Mirroring,
+    // Four screen cartridge has its own VRAM, and ignores mirroring register.
+    is_four_screen: bool,
+    prg_ram_protect: Byte,
+    irq_latch: Byte,
+    irq_counter: Byte,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq: bool,
+}
+
+impl Mmc3 {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            is_four_screen: cartridge.mirroring == Mirroring::FourScreen,
+            mirroring: cartridge.mirroring,
+            prg_ram_protect: 0b1000_0000,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+        }
+    }
+
+    // Bit 7 enables PRG RAM, and bit 6 protects it from writes.
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.prg_ram_protect & 0b1000_0000 != 0
+    }
+
+    fn is_prg_ram_writable(&self) -> bool {
+        self.is_prg_ram_enabled() && self.prg_ram_protect & 0b0100_0000 == 0
+    }
+
+    // Registers are selected by address range and whether address is even or odd.
+    fn write_register(&mut self, addr: Address, value: Byte) {
+        match (addr, addr & 1 == 0) {
+            (0x8000..=0x9FFF, true)  => self.bank_select = value,
+            (0x8000..=0x9FFF, false) => self.banks[(self.bank_select & 0b111) as usize] = value,
+            (0xA000..=0xBFFF, true)  => {
+                if !self.is_four_screen {
+                    self.mirroring = if value & 1 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal };
+                }
+            }
+            (0xA000..=0xBFFF, false) => self.prg_ram_protect = value,
+            (0xC000..=0xDFFF, true)  => self.irq_latch = value,
+            // Counter is reloaded at next clock.
+            (0xC000..=0xDFFF, false) => {
+                self.irq_counter = 0;
+                self.irq_reload  = true;
+            }
+            // Disabling also acknowledges pending irq.
+            (_, true) => {
+                self.irq_enabled = false;
+                self.irq         = false;
+            }
+            (_, false) => self.irq_enabled = true,
+        }
+    }
+
+    fn prg_index(&self, addr: Address) -> usize {
+        let second_last = (self.prg_rom.len() / PRG_BANK_SIZE).saturating_sub(2);
+        // Bit 6 swaps 0x8000 and 0xC000.
+        let is_swapped  = self.bank_select & 0b0100_0000 != 0;
+        let bank = match (addr, is_swapped) {
+            (0x8000..=0x9FFF, false) | (0xC000..=0xDFFF, true) => self.banks[6] as usize,
+            (0x8000..=0x9FFF, true) | (0xC000..=0xDFFF, false) => second_last,
+            (0xA000..=0xBFFF, _) => self.banks[7] as usize,
+            _ => second_last + 1,
+        };
+        bank_index(self.prg_rom.len(), bank, PRG_BANK_SIZE, addr)
+    }
+
+    fn chr_bank(&self, addr: Address) -> usize {
+        // Bit 7 swaps 2KB banks at 0x0000 and 1KB banks at 0x1000.
+        let addr = if self.bank_select & 0b1000_0000 != 0 { addr ^ 0x1000 } else { addr };
+        // 2KB banks ignore low bit of the bank.
+        match addr & 0x1FFF {
+            0x0000..=0x07FF => (self.banks[0] & !1) as usize | ((addr as usize / CHR_BANK_SIZE) & 1),
+            0x0800..=0x0FFF => (self.banks[1] & !1) as usize | ((addr as usize / CHR_BANK_SIZE) & 1),
+            _ => self.banks[2 + (addr as usize & 0x0FFF) / CHR_BANK_SIZE] as usize,
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => Some(self.prg_ram[addr as usize % PRG_RAM_SIZE]),
+            0x8000..=0xFFFF => self.prg_rom.get(self.prg_index(addr)).copied(),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_writable() => self.prg_ram[addr as usize % PRG_RAM_SIZE] = value,
+            0x8000..=0xFFFF => self.write_register(addr, value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read(self.chr_bank(addr), CHR_BANK_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write(self.chr_bank(addr), CHR_BANK_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    // Clock scanline counter. Irq is raised when the counter becomes 0.
+    fn a12_rise(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload  = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 128KB PRG ROM and 128KB CHR ROM, whose bytes are the bank number
+    fn new_mmc3(mirroring: Mirroring) -> Mmc3 {
+        let prg_rom = bank_numbered_rom(0x20000, PRG_BANK_SIZE);
+        let chr_rom = bank_numbered_rom(0x20000, CHR_BANK_SIZE);
+        Mmc3::new(Cartoridge { prg_rom, chr_rom, mapper: 4, mirroring, ..Default::default() })
+    }
+
+    fn set_bank(mmc3: &mut Mmc3, mode: Byte, register: Byte, bank: Byte) {
+        mmc3.cpu_write(0x8000, mode | register);
+        mmc3.cpu_write(0x8001, bank);
+    }
+
+    #[test]
+    fn test_prg_banks() {
+        let mut mmc3 = new_mmc3(Mirroring::Vertical);
+        set_bank(&mut mmc3, 0x00, 6, 3);
+        set_bank(&mut mmc3, 0x00, 7, 5);
+
+        assert_eq!(mmc3.cpu_read(0x8000), Some(3));
+        assert_eq!(mmc3.cpu_read(0xA000), Some(5));
+        assert_eq!(mmc3.cpu_read(0xC000), Some(14));
+        assert_eq!(mmc3.cpu_read(0xE000), Some(15));
+
+        // Swap 0x8000 and 0xC000
+        mmc3.cpu_write(0x8000, 0b0100_0000);
+        assert_eq!(mmc3.cpu_read(0x8000), Some(14));
+        assert_eq!(mmc3.cpu_read(0xC000), Some(3));
+        assert_eq!(mmc3.cpu_read(0xFFFF), Some(15));
+    }
+
+    #[test]
+    fn test_chr_banks() {
+        let mut mmc3 = new_mmc3(Mirroring::Vertical);
+        for (register, bank) in [(0, 9), (1, 20), (2, 40), (3, 41), (4, 42), (5, 43)] {
+            set_bank(&mut mmc3, 0x00, register, bank);
+        }
+
+        assert_eq!(mmc3.ppu_read(0x0000), 8);
+        assert_eq!(mmc3.ppu_read(0x0400), 9);
+        assert_eq!(mmc3.ppu_read(0x0800), 20);
+        assert_eq!(mmc3.ppu_read(0x0C00), 21);
+        assert_eq!(mmc3.ppu_read(0x1000), 40);
+        assert_eq!(mmc3.ppu_read(0x1FFF), 43);
+
+        // Swap 0x0000 and 0x1000
+        mmc3.cpu_write(0x8000, 0b1000_0000);
+        assert_eq!(mmc3.ppu_read(0x0000), 40);
+        assert_eq!(mmc3.ppu_read(0x1400), 9);
+        assert_eq!(mmc3.ppu_read(0x1800), 20);
+    }
+
+    #[test]
+    fn test_mirroring() {
+        let mut mmc3 = new_mmc3(Mirroring::Vertical);
+        mmc3.cpu_write(0xA000, 1);
+        assert_eq!(mmc3.mirroring(), Mirroring::Horizontal);
+
+        let mut mmc3 = new_mmc3(Mirroring::FourScreen);
+        mmc3.cpu_write(0xA000, 1);
+        assert_eq!(mmc3.mirroring(), Mirroring::FourScreen);
+    }
+
+    #[test]
+    fn test_prg_ram_protect() {
+        let mut mmc3 = new_mmc3(Mirroring::Vertical);
+        mmc3.cpu_write(0x6000, 0x42);
+        assert_eq!(mmc3.cpu_read(0x6000), Some(0x42));
+
+        mmc3.cpu_write(0xA001, 0b1100_0000);
+        mmc3.cpu_write(0x6000, 0x00);
+        assert_eq!(mmc3.cpu_read(0x6000), Some(0x42));
+
+        mmc3.cpu_write(0xA001, 0b0000_0000);
+        assert_eq!(mmc3.cpu_read(0x6000), None);
+    }
+
+    #[test]
+    fn test_scanline_irq() {
+        let mut mmc3 = new_mmc3(Mirroring::Vertical);
+        mmc3.cpu_write(0xC000, 2);
+        mmc3.cpu_write(0xC001, 0);
+        mmc3.cpu_write(0xE001, 0);
+
+        // Reload to 2, then count down to 0
+        mmc3.a12_rise();
+        mmc3.a12_rise();
+        assert!(!mmc3.irq());
+        mmc3.a12_rise();
+        assert!(mmc3.irq());
+
+        mmc3.cpu_write(0xE000, 0);
+        assert!(!mmc3.irq());
+
+        // Counter is reloaded after 0, and irq isn't raised while disabled.
+        for _ in 0..3 {
+            mmc3.a12_rise();
+        }
+        assert!(!mmc3.irq());
+    }
+
+    #[test]
+    fn test_a12_watcher() {
+        let mut watcher = A12Watcher::new();
+
+        assert!(watcher.update(0x1000, 100));
+        assert!(!watcher.update(0x1010, 102));
+        // Nametable fetch between sprite fetches is too short.
+        assert!(!watcher.update(0x2000, 104));
+        assert!(!watcher.update(0x1020, 108));
+        assert!(!watcher.update(0x0000, 200));
+        assert!(watcher.update(0x1000, 300));
+    }
+
+    #[test]
+    fn test_a12_watcher_dot_restarts() {
+        let mut watcher = A12Watcher::new();
+        watcher.update(0x1000, 100);
+        watcher.update(0x0000, 200);
+
+        // Dot counter restarted from 0, e.g. on a new frame.
+        assert!(watcher.update(0x1000, 4));
+        watcher.update(0x0000, 20);
+        assert!(watcher.update(0x1000, 40));
+    }
+}
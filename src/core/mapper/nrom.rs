@@ -0,0 +1,98 @@
+//! A module that provide NROM (mapper 0), which has no bank switching
+
+use super::{bank_index, Chr, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_ROM_SIZE: usize = 0x8000;
+const CHR_SIZE:     usize = 0x2000;
+
+/// NROM with 16KB or 32KB PRG ROM, and 8KB CHR ROM or CHR RAM
+pub struct Nrom {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            mirroring: cartridge.mirroring,
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    // 16KB PRG ROM is mirrored to 0xC000-0xFFFF
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x8000..=0xFFFF => self.prg_rom.get(bank_index(self.prg_rom.len(), 0, PRG_ROM_SIZE, addr)).copied(),
+            _ => None,
+        }
+    }
+
+    // Writing to rom has no effect
+    fn cpu_write(&mut self, _addr: Address, _value: Byte) {}
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read(0, CHR_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write(0, CHR_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    fn new_nrom(prg_size: usize, chr_size: usize) -> Nrom {
+        let prg_rom = bank_numbered_rom(prg_size, 0x1000);
+        let chr_rom = bank_numbered_rom(chr_size, 0x0400);
+        Nrom::new(Cartoridge { prg_rom, chr_rom, mapper: 0, mirroring: Mirroring::Horizontal, ..Default::default() })
+    }
+
+    #[test]
+    fn test_prg_rom_16kb_is_mirrored() {
+        let nrom = new_nrom(0x4000, 0x2000);
+
+        assert_eq!(nrom.cpu_read(0x8000), Some(0x00));
+        assert_eq!(nrom.cpu_read(0xBFFF), Some(0x03));
+        assert_eq!(nrom.cpu_read(0xC000), Some(0x00));
+        assert_eq!(nrom.cpu_read(0xF000), Some(0x03));
+        assert_eq!(nrom.cpu_read(0x6000), None);
+    }
+
+    #[test]
+    fn test_prg_rom_32kb() {
+        let mut nrom = new_nrom(0x8000, 0x2000);
+        nrom.cpu_write(0xC000, 0xFF);
+
+        assert_eq!(nrom.cpu_read(0xC000), Some(0x04));
+        assert_eq!(nrom.cpu_read(0xFFFF), Some(0x07));
+    }
+
+    #[test]
+    fn test_chr_rom_is_read_only() {
+        let mut nrom = new_nrom(0x4000, 0x2000);
+        nrom.ppu_write(0x1C00, 0xFF);
+
+        assert_eq!(nrom.ppu_read(0x1C00), 0x07);
+    }
+
+    #[test]
+    fn test_chr_ram() {
+        let mut nrom = new_nrom(0x4000, 0);
+        nrom.ppu_write(0x1C00, 0xFF);
+
+        assert_eq!(nrom.ppu_read(0x1C00), 0xFF);
+    }
+}
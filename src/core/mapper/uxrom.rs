@@ -0,0 +1,118 @@
+//! A module that provide UxROM (mapper 2), which switches 16KB PRG bank at 0x8000
+
+use super::{bank_index, Chr, Latch, Mapper};
+use crate::core::cartridge::{Cartoridge, Mirroring};
+use crate::core::types::*;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_SIZE:      usize = 0x2000;
+
+/// UxROM with switchable PRG bank at 0x8000 and the last bank fixed at 0xC000
+pub struct Uxrom {
+    prg_rom: Vec<Byte>,
+    chr: Chr,
+    mirroring: Mirroring,
+    // PRG bank at 0x8000
+    latch: Latch,
+}
+
+impl Uxrom {
+    pub fn new(cartridge: Cartoridge) -> Self {
+        Self {
+            prg_rom: cartridge.prg_rom,
+            chr: Chr::new(cartridge.chr_rom),
+            mirroring: cartridge.mirroring,
+            latch: Latch::default(),
+        }
+    }
+
+    /// Set whether the board has bus conflicts.
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.latch.bus_conflicts = enabled;
+    }
+
+    fn prg_index(&self, addr: Address) -> usize {
+        let bank = match addr {
+            0x8000..=0xBFFF => self.latch.value as usize,
+            _ => (self.prg_rom.len() / PRG_BANK_SIZE).saturating_sub(1),
+        };
+        bank_index(self.prg_rom.len(), bank, PRG_BANK_SIZE, addr)
+    }
+}
+
+impl Mapper for Uxrom {
+    fn cpu_read(&self, addr: Address) -> Option<Byte> {
+        match addr {
+            0x8000..=0xFFFF => self.prg_rom.get(self.prg_index(addr)).copied(),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: Address, value: Byte) {
+        if let Some(rom) = self.cpu_read(addr) {
+            self.latch.write(rom, value);
+        }
+    }
+
+    fn ppu_read(&self, addr: Address) -> Byte {
+        self.chr.read(0, CHR_SIZE, addr)
+    }
+
+    fn ppu_write(&mut self, addr: Address, value: Byte) {
+        self.chr.write(0, CHR_SIZE, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::bank_numbered_rom;
+
+    // 128KB PRG ROM whose bytes are the bank number, and CHR RAM
+    fn new_uxrom() -> Uxrom {
+        let prg_rom = bank_numbered_rom(0x20000, PRG_BANK_SIZE);
+        Uxrom::new(Cartoridge { prg_rom, chr_rom: Vec::new(), mapper: 2, mirroring: Mirroring::Vertical, ..Default::default() })
+    }
+
+    #[test]
+    fn test_prg_bank() {
+        let mut uxrom = new_uxrom();
+        uxrom.cpu_write(0x8000, 5);
+
+        assert_eq!(uxrom.cpu_read(0x8000), Some(5));
+        assert_eq!(uxrom.cpu_read(0xBFFF), Some(5));
+        assert_eq!(uxrom.cpu_read(0xC000), Some(7));
+        assert_eq!(uxrom.cpu_read(0xFFFF), Some(7));
+    }
+
+    #[test]
+    fn test_prg_rom_smaller_than_bank() {
+        let prg_rom = bank_numbered_rom(0x2000, 0x1000);
+        let uxrom   = Uxrom::new(Cartoridge { prg_rom, mapper: 2, ..Default::default() });
+
+        assert_eq!(uxrom.cpu_read(0xC000), Some(0));
+        assert_eq!(uxrom.cpu_read(0xF000), Some(1));
+    }
+
+    #[test]
+    fn test_chr_ram() {
+        let mut uxrom = new_uxrom();
+        uxrom.ppu_write(0x1234, 0x42);
+
+        assert_eq!(uxrom.ppu_read(0x1234), 0x42);
+    }
+
+    #[test]
+    fn test_bus_conflicts() {
+        let mut uxrom = new_uxrom();
+        uxrom.set_bus_conflicts(true);
+        // The last bank has 7 at 0xC000
+        uxrom.cpu_write(0xC000, 0b1110);
+
+        assert_eq!(uxrom.cpu_read(0x8000), Some(6));
+    }
+}
@@ -1,5 +1,3 @@
-mod core;
-
 fn main() {
     println!("Hello, world!");
 }
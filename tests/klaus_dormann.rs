@@ -0,0 +1,153 @@
+//! Run Klaus Dormann's 6502 test suite against the cpu.
+//!
+//! See https://github.com/Klaus2m5/6502_65C02_functional_tests for the suite.
+//! The binaries are not bundled, so put prebuilt `6502_functional_test.bin`,
+//! `6502_decimal_test.bin` and `6502_interrupt_test.bin` into the directory
+//! specified by `KLAUS_TEST_DIR` (default: `tests/klaus`), then run
+//!
+//! ```sh
+//! cargo test --release --test klaus_dormann -- --ignored
+//! ```
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use rust_nes_emulator::core::cpu::{Cpu, CpuVariant};
+use rust_nes_emulator::core::cpu::memory::Memory;
+use rust_nes_emulator::core::types::*;
+
+// The suite has feedback port at this address to request interrupts.
+// Bit 0 drives irq and bit 1 drives nmi.
+const FEEDBACK_PORT: Address = 0xBFFC;
+
+// Flat 64KB memory. The content is shared with the harness, so it can be
+// inspected while the cpu owns the memory.
+#[derive(Clone)]
+struct FlatMemory {
+    bytes: Rc<RefCell<Vec<Byte>>>,
+}
+
+impl FlatMemory {
+    fn new(image: &[Byte], load: Address) -> Self {
+        let mut bytes = vec![0; 0x10000];
+        let load      = load as usize;
+        bytes[load..(load + image.len())].copy_from_slice(image);
+        Self { bytes: Rc::new(RefCell::new(bytes)) }
+    }
+}
+
+impl Memory for FlatMemory {
+    fn read_byte(&self, addr: Address) -> Byte {
+        self.bytes.borrow()[addr as usize]
+    }
+
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        self.bytes.borrow_mut()[addr as usize] = value;
+    }
+}
+
+struct Harness {
+    cpu: Cpu<FlatMemory>,
+    mem: FlatMemory,
+    feedback: bool,
+}
+
+impl Harness {
+    // Load image at `load` and start execution from `start`.
+    fn new(image: &[Byte], load: Address, start: Address) -> Self {
+        let mut mem = FlatMemory::new(image, load);
+        // The suite set reset vector to trap, so overwrite it to start the test.
+        mem.write_word(0xFFFC, start);
+
+        // The suite is written for nmos 6502 which has decimal mode.
+        let mut cpu = Cpu::with_variant(mem.clone(), CpuVariant::Nmos6502);
+        cpu.power_on();
+        Self { cpu, mem, feedback: false }
+    }
+
+    // Connect the feedback port to interrupt lines.
+    fn with_feedback(mut self) -> Self {
+        self.mem.write_byte(FEEDBACK_PORT, 0);
+        self.feedback = true;
+        self
+    }
+
+    // Run until the program counter doesn't change after an instruction, and
+    // return the address. Panic if it doesn't trap in `limit` cycles.
+    fn run_until_trap(&mut self, limit: u64) -> Address {
+        while self.cpu.cycles() < limit {
+            let pc = self.cpu.pc();
+            if let Err(err) = self.cpu.step() {
+                panic!("{} after {} cycles", err, self.cpu.cycles());
+            }
+
+            if self.feedback {
+                let port = self.mem.read_byte(FEEDBACK_PORT);
+                self.cpu.set_irq_line(port & 0b01 != 0);
+                self.cpu.set_nmi_line(port & 0b10 != 0);
+            }
+
+            if self.cpu.pc() == pc {
+                return pc;
+            }
+        }
+        panic!("No trap in {} cycles. Last pc is 0x{:04X}", limit, self.cpu.pc());
+    }
+}
+
+fn load_binary(name: &str) -> Vec<Byte> {
+    let dir  = env::var("KLAUS_TEST_DIR").unwrap_or_else(|_| "tests/klaus".to_string());
+    let path = PathBuf::from(dir).join(name);
+    fs::read(&path).unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err))
+}
+
+#[test]
+fn test_harness_traps_on_self_loop() {
+    // lda #$42; sta $10; jmp $0405
+    let program = [0xA9, 0x42, 0x85, 0x10, 0x4C, 0x04, 0x04];
+    let mut harness = Harness::new(&program, 0x0400, 0x0400);
+
+    assert_eq!(harness.run_until_trap(1000), 0x0404);
+    assert_eq!(harness.mem.read_byte(0x0010), 0x42);
+}
+
+#[test]
+fn test_harness_feedback_port_requests_irq() {
+    // cli; lda #$01; sta $BFFC; jmp $0406
+    let program = [0x58, 0xA9, 0x01, 0x8D, 0xFC, 0xBF, 0x4C, 0x06, 0x04];
+    let mut harness = Harness::new(&program, 0x0400, 0x0400).with_feedback();
+    // irq handler: lda #$00; sta $BFFC; lda #$42; sta $10; jmp $0509
+    let handler = [0xA9, 0x00, 0x8D, 0xFC, 0xBF, 0xA9, 0x42, 0x85, 0x10, 0x4C, 0x09, 0x05];
+    for (i, byte) in handler.iter().enumerate() {
+        harness.mem.write_byte(0x0500 + i as Address, *byte);
+    }
+    harness.mem.write_word(0xFFFE, 0x0500);
+
+    assert_eq!(harness.run_until_trap(1000), 0x0509);
+    assert_eq!(harness.mem.read_byte(0x0010), 0x42);
+}
+
+#[test]
+#[ignore]
+fn test_functional() {
+    let mut harness = Harness::new(&load_binary("6502_functional_test.bin"), 0x0000, 0x0400);
+    assert_eq!(harness.run_until_trap(200_000_000), 0x3469);
+}
+
+#[test]
+#[ignore]
+fn test_decimal() {
+    let mut harness = Harness::new(&load_binary("6502_decimal_test.bin"), 0x0200, 0x0200);
+    harness.run_until_trap(200_000_000);
+    // The test store 0 to ERROR if all results are correct
+    assert_eq!(harness.mem.read_byte(0x000B), 0);
+}
+
+#[test]
+#[ignore]
+fn test_interrupt() {
+    let mut harness = Harness::new(&load_binary("6502_interrupt_test.bin"), 0x0000, 0x0400).with_feedback();
+    assert_eq!(harness.run_until_trap(10_000_000), 0x06F5);
+}